@@ -1,971 +1,3910 @@
 #![allow(unused)]
 
+use crate::codec::*;
+use crate::polling::DutySampler;
 use crate::prelude::*;
 use crate::registers::*;
-use byteorder::{ByteOrder, LittleEndian};
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
 #[cfg(not(feature = "async"))]
 use embedded_hal::i2c::I2c;
 #[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+#[cfg(feature = "async")]
 use embedded_hal_async::i2c::I2c;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MCP23017<I2C, State = Configuring> {
     i2c: I2C,
     address: u8,
+    read_style: ReadStyle,
+    spurious_interrupts: u32,
+    output_inversion: u16,
+    staged_interrupts: u16,
+    post_write_verify: PostWriteVerify,
+    write_corrections: u32,
+    output_cache: Option<u16>,
     state: core::marker::PhantomData<State>,
 }
 
-#[maybe_async_cfg::maybe(
-    sync(cfg(not(feature = "async")), keep_self,),
-    async(feature = "async", keep_self)
-)]
-trait RegReadWrite {
-    async fn write_config(&mut self, register: Register, value: u16) -> Result<(), Error>;
-    async fn read_config(&mut self, register: Register) -> Result<u16, Error>;
+/// Suspicious configuration found by lint_config
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConfigDiagnostic {
+    InterruptOnOutput(MyPort, PinNumber),
+    PullUpOnOutput(MyPort, PinNumber),
+    DefvalIgnored(MyPort, PinNumber),
 }
 
-impl<I2C, E, State> MCP23017<I2C, State>
-where
-    I2C: I2c<Error = E>,
-{
-    /**
-     * Function used to create a new handler for chip/port/pin
-     */
-    #[inline]
-    pub fn new(i2c: I2C, address: u8) -> Self {
-        MCP23017 {
-            i2c,
-            address,
-            state: Default::default(),
+/// Iterator over the diagnostics of a register state read by lint_config
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigLint {
+    iodir: u16,
+    gpinten: u16,
+    intcon: u16,
+    defval: u16,
+    gppu: u16,
+    index: u8,
+}
+
+impl Iterator for ConfigLint {
+    type Item = ConfigDiagnostic;
+
+    fn next(&mut self) -> Option<ConfigDiagnostic> {
+        while self.index < 48 {
+            let check = self.index / 16;
+            let bit = self.index % 16;
+            self.index += 1;
+
+            let mask = 1 << bit;
+            let port = if bit < 8 {
+                MyPort::Porta
+            } else {
+                MyPort::Portb
+            };
+            let Some(pin) = pin_mask_to_number(PinMask::from(1 << (bit % 8))) else {
+                continue;
+            };
+            let output = self.iodir & mask == 0;
+
+            match check {
+                0 if output && self.gpinten & mask != 0 => {
+                    return Some(ConfigDiagnostic::InterruptOnOutput(port, pin))
+                }
+                1 if output && self.gppu & mask != 0 => {
+                    return Some(ConfigDiagnostic::PullUpOnOutput(port, pin))
+                }
+                2 if self.intcon & mask == 0 && self.defval & mask != 0 => {
+                    return Some(ConfigDiagnostic::DefvalIgnored(port, pin))
+                }
+                _ => {}
+            }
         }
+        None
     }
 }
 
-#[maybe_async_cfg::maybe(
-    sync(cfg(not(feature = "async")), self = "MCP23017",),
-    async(feature = "async", keep_self)
-)]
-impl<I2C, E, State> RegReadWrite for MCP23017<I2C, State>
-where
-    I2C: I2c<Error = E>,
-{
+/// Output waveform with every I2C write already encoded, holding up to N frames
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformPlan<const N: usize> {
+    frames: [[u8; 3]; N],
+    len: usize,
+}
+
+impl<const N: usize> WaveformPlan<N> {
     /**
-     * Private function used to read the chip registers using i2c
+     * Function used to get the number of frames in the plan
      */
     #[inline]
-    async fn read_config(&mut self, register: Register) -> Result<u16, Error> {
-        let register_address = register as u8;
-        let mut rx_buffer: [u8; 2] = [0; 2];
-        self.i2c
-            .write_read(self.address, &[register_address], &mut rx_buffer)
-            .await
-            .map_err(i2c_comm_error)?;
-        Ok(LittleEndian::read_u16(&rx_buffer))
+    pub fn len(&self) -> usize {
+        self.len
     }
 
     /**
-     * Private function used to write the chip registers using i2c
+     * Function used to verify if the plan has no frames
      */
     #[inline]
-    async fn write_config(&mut self, register: Register, value: u16) -> Result<(), Error> {
-        let register_address = register as u8;
-        self.i2c
-            .write(
-                self.address,
-                &[
-                    register_address,
-                    value.to_le_bytes()[0],
-                    value.to_le_bytes()[1],
-                ],
-            )
-            .await
-            .map_err(i2c_comm_error)?;
-        Ok(())
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 }
 
-#[allow(dead_code)]
-#[maybe_async_cfg::maybe(
-    sync(cfg(not(feature = "async")), self = "MCP23017",),
-    async(feature = "async", keep_self)
-)]
-impl<I2C, E> MCP23017<I2C, Configuring>
-where
-    I2C: I2c<Error = E>,
-{
+/// Single step of an output sequence: pins in mask are driven to value, then held for min_dwell_us
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SequenceStep {
+    pub mask: u16,
+    pub value: u16,
+    pub min_dwell_us: u32,
+}
+
+/// Final outcome of an output sequence
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SequenceOutcome {
+    Completed,
+    VerifyFailed {
+        step: usize,
+        expected: u16,
+        read_back: u16,
+    },
+}
+
+/// Result of an output sequence, crc covers every verified output word (CRC-16/CCITT-FALSE)
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SequenceReport {
+    pub steps_applied: usize,
+    pub crc: u16,
+    pub outcome: SequenceOutcome,
+}
+
+/// Output writes staged over both ports and applied all-or-nothing as far as the bus allows,
+/// values are logical (output inversion applied)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct OutputTransaction {
+    mask: u16,
+    value: u16,
+}
+
+impl OutputTransaction {
     /**
-     * Function used to set the chip/port/pin as input
+     * Function used to create an empty transaction
      */
     #[inline]
-    pub async fn set_as_input(mut self) -> Result<MCP23017<I2C, InputConfiguring>, Error> {
-        self.write_config(Register::Iodir, 0xFFFF).await?;
-
-        Ok(MCP23017 {
-            i2c: self.i2c,
-            address: self.address,
-            state: core::marker::PhantomData::<InputConfiguring>,
-        })
+    pub const fn new() -> Self {
+        OutputTransaction { mask: 0, value: 0 }
     }
 
     /**
-     * Function used to set the chip/port/pin as output
+     * Function used to stage the value of a pin
      */
     #[inline]
-    pub async fn set_as_output(mut self) -> Result<MCP23017<I2C, OutputReady>, Error> {
-        self.write_config(Register::Iodir, 0x0000).await?;
-
-        Ok(MCP23017 {
-            i2c: self.i2c,
-            address: self.address,
-            state: core::marker::PhantomData::<OutputReady>,
-        })
+    pub fn with_pin(self, port: MyPort, pin: PinNumber, value: PinSet) -> Self {
+        OutputTransaction {
+            mask: u16::from(GpioPair(self.mask).with_pin(port, pin, PinSet::High)),
+            value: u16::from(GpioPair(self.value).with_pin(port, pin, value)),
+        }
     }
-}
 
-#[maybe_async_cfg::maybe(
-    sync(cfg(not(feature = "async")), self = "MCP23017",),
-    async(feature = "async", keep_self)
-)]
-impl<I2C, E> MCP23017<I2C, OutputReady>
-where
-    I2C: I2c<Error = E>,
-{
     /**
-     * Function used to write the output value to be set on chip/port/pin
+     * Function used to stage the value of a whole port
      */
     #[inline]
-    pub async fn write(&mut self, value: u16) -> Result<(), Error> {
-        self.write_config(Register::Gpio, value)
-            .await
-            .map_err(i2c_comm_error)?;
-        Ok(())
+    pub fn with_port(self, port: MyPort, value: u8) -> Self {
+        OutputTransaction {
+            mask: set_port_byte(self.mask, port, 0xFF),
+            value: set_port_byte(self.value, port, value),
+        }
     }
 
     /**
-     * Function used to write the output value to be set on pin
+     * Function used to verify if no pin is staged
      */
     #[inline]
-    pub async fn write_pin(
-        &mut self,
-        port: MyPort,
-        pin: PinNumber,
-        value: PinSet,
-    ) -> Result<(), Error> {
-        let mut result = self.read_config(Register::Gpio).await?;
-
-        let mut res = result.to_le_bytes();
-        result = match (port, value) {
-            (MyPort::Porta, PinSet::High) => {
-                res[0] = bit_set(res[0], pin);
-                LittleEndian::read_u16(&res)
-            }
-            (MyPort::Porta, PinSet::Low) => {
-                res[0] = bit_clear(res[0], pin);
-                LittleEndian::read_u16(&res)
-            }
-            (MyPort::Portb, PinSet::High) => {
-                res[1] = bit_set(res[1], pin);
-                LittleEndian::read_u16(&res)
-            }
-            (MyPort::Portb, PinSet::Low) => {
-                res[1] = bit_clear(res[1], pin);
-                LittleEndian::read_u16(&res)
-            }
-        };
-
-        self.write_config(Register::Gpio, result)
-            .await
-            .map_err(i2c_comm_error)?;
-        Ok(())
+    pub fn is_empty(&self) -> bool {
+        self.mask == 0
     }
 }
 
-#[maybe_async_cfg::maybe(
-    sync(cfg(not(feature = "async")), self = "MCP23017",),
-    async(feature = "async", keep_self)
-)]
-impl<I2C, E> MCP23017<I2C, InputConfiguring>
-where
-    I2C: I2c<Error = E>,
-{
-    /**
-     * Function used to set the pull on the input
-     */
-    #[inline]
-    pub async fn set_pull(mut self, pull: PinSet) -> Result<Self, Error> {
-        let result = match pull {
-            PinSet::High => 0xFFFF,
-            PinSet::Low => 0x0000,
-        };
+/// Final outcome of an output transaction
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TransactionOutcome {
+    Committed,
+    RolledBack,
+    RollbackFailed,
+}
 
-        self.write_config(Register::Gppu, result).await?;
+/// Result of an output transaction, output is the logical output word as last known by the driver
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TransactionReport {
+    pub outcome: TransactionOutcome,
+    pub output: u16,
+}
 
-        Ok(self)
+/// Complete chip configuration (IODIR to GPPU) applied by apply_config in a single burst,
+/// unset registers keep their power-on value
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigBuilder {
+    iodir: u16,
+    ipol: u16,
+    gpinten: u16,
+    defval: u16,
+    intcon: u16,
+    iocon: u8,
+    gppu: u16,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
+impl ConfigBuilder {
     /**
-     * Function used to set the interrupt mirror function on the input
+     * Function used to create a configuration holding the power-on register values
      */
     #[inline]
-    pub async fn set_interrupt_mirror(mut self, mirror: InterruptMirror) -> Result<Self, Error> {
-        let mut reg = self.read_config(Register::Iocon).await?;
-
-        let mut regres = reg.to_le_bytes();
-        match mirror {
-            InterruptMirror::MirrorOn => {
-                regres[0] |= InterruptMirror::MirrorOn as u8;
-                regres[1] |= InterruptMirror::MirrorOn as u8;
-            }
-            InterruptMirror::MirrorOff => {
-                regres[0] &= !(InterruptMirror::MirrorOn as u8);
-                regres[1] &= !(InterruptMirror::MirrorOn as u8);
-            }
+    pub const fn new() -> Self {
+        ConfigBuilder {
+            iodir: 0xFFFF,
+            ipol: 0,
+            gpinten: 0,
+            defval: 0,
+            intcon: 0,
+            iocon: 0,
+            gppu: 0,
         }
-        reg = LittleEndian::read_u16(&regres);
-
-        self.write_config(Register::Iocon, reg).await?;
-
-        Ok(self)
     }
 
     /**
-     * Function used to choose the pin as interrupt on the input
+     * Function used to set the pin directions (1 = input)
      */
     #[inline]
-    pub async fn set_interrupt_on(
-        mut self,
-        port: MyPort,
-        pin: PinNumber,
-        interrupt_on: InterruptOn,
-    ) -> Result<Self, Error> {
-        let mut reg = self.read_config(Register::Intcon).await?;
-
-        let mut regres = reg.to_le_bytes();
-        reg = match (port, interrupt_on) {
-            (MyPort::Porta, InterruptOn::PinChange) => {
-                regres[0] = bit_clear(regres[0], pin);
-                LittleEndian::read_u16(&regres)
-            }
-            (MyPort::Porta, InterruptOn::ChangeFromRegister) => {
-                regres[0] = bit_set(regres[0], pin);
-                LittleEndian::read_u16(&regres)
-            }
-            (MyPort::Portb, InterruptOn::PinChange) => {
-                regres[1] = bit_clear(regres[1], pin);
-                LittleEndian::read_u16(&regres)
-            }
-            (MyPort::Portb, InterruptOn::ChangeFromRegister) => {
-                regres[1] = bit_set(regres[1], pin);
-                LittleEndian::read_u16(&regres)
-            }
-        };
-
-        self.write_config(Register::Intcon, reg).await?;
-        Ok(self)
+    pub fn with_iodir(mut self, value: impl Into<IodirPair>) -> Self {
+        self.iodir = value.into().into();
+        self
     }
 
     /**
-     * Function used to set the interrupt compare function on the input
+     * Function used to set the input polarities (1 = inverted)
      */
     #[inline]
-    pub async fn set_interrupt_compare(
-        mut self,
-        port: MyPort,
-        pin: PinNumber,
-        value: PinSet,
-    ) -> Result<Self, Error> {
-        let intcon = self.read_config(Register::Intcon).await?.to_le_bytes();
+    pub fn with_ipol(mut self, value: impl Into<IpolPair>) -> Self {
+        self.ipol = value.into().into();
+        self
+    }
 
-        match port {
-            MyPort::Porta => {
-                if bit_read(intcon[0], pin) != 1 {
-                    return Err(Error::InvalidInterruptSetting);
-                }
-            }
-            MyPort::Portb => {
-                if bit_read(intcon[1], pin) != 1 {
-                    return Err(Error::InvalidInterruptSetting);
-                }
-            }
-        }
+    /**
+     * Function used to set the interrupt enables
+     */
+    #[inline]
+    pub fn with_gpinten(mut self, value: impl Into<GpintenPair>) -> Self {
+        self.gpinten = value.into().into();
+        self
+    }
 
-        let mut reg = self.read_config(Register::Defval).await?.to_le_bytes(); //change only valid if intcon is set to 1
+    /**
+     * Function used to set the interrupt compare values
+     */
+    #[inline]
+    pub fn with_defval(mut self, value: impl Into<DefvalPair>) -> Self {
+        self.defval = value.into().into();
+        self
+    }
 
-        match (port, value) {
-            (MyPort::Porta, PinSet::High) => {
-                reg[0] = bit_set(reg[0], pin);
-            }
-            (MyPort::Porta, PinSet::Low) => {
-                reg[0] = bit_clear(reg[0], pin);
-            }
-            (MyPort::Portb, PinSet::High) => {
-                reg[1] = bit_set(reg[1], pin);
-            }
-            (MyPort::Portb, PinSet::Low) => {
-                reg[1] = bit_clear(reg[1], pin);
-            }
-        };
+    /**
+     * Function used to set the interrupt compare sources (1 = compare against DEFVAL)
+     */
+    #[inline]
+    pub fn with_intcon(mut self, value: impl Into<IntconPair>) -> Self {
+        self.intcon = value.into().into();
+        self
+    }
 
-        self.write_config(Register::Defval, LittleEndian::read_u16(&reg))
-            .await?;
-        Ok(self)
+    /**
+     * Function used to set the IOCON byte, written to both IOCON addresses
+     */
+    #[inline]
+    pub fn with_iocon(mut self, value: u8) -> Self {
+        self.iocon = value;
+        self
     }
 
     /**
-     * Function used to set input to the ready state
+     * Function used to set the pull-up enables (1 = pull-up), built per pin or port with
+     * GppuPair, a raw u16 holds Port A in the low byte and Port B in the high byte
      */
     #[inline]
-    pub fn ready(mut self) -> MCP23017<I2C, InputReady> {
-        MCP23017 {
-            i2c: self.i2c,
-            address: self.address,
-            state: core::marker::PhantomData::<InputReady>,
+    pub fn with_gppu(mut self, value: impl Into<GppuPair>) -> Self {
+        self.gppu = value.into().into();
+        self
+    }
+}
+
+/// Copy of the whole register file (bank 0 order) taken by dump_registers
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegisterSnapshot {
+    pub iodira: u8,
+    pub iodirb: u8,
+    pub ipola: u8,
+    pub ipolb: u8,
+    pub gpintena: u8,
+    pub gpintenb: u8,
+    pub defvala: u8,
+    pub defvalb: u8,
+    pub intcona: u8,
+    pub intconb: u8,
+    pub iocona: u8,
+    pub ioconb: u8,
+    pub gppua: u8,
+    pub gppub: u8,
+    pub intfa: u8,
+    pub intfb: u8,
+    pub intcapa: u8,
+    pub intcapb: u8,
+    pub gpioa: u8,
+    pub gpiob: u8,
+    pub olata: u8,
+    pub olatb: u8,
+}
+
+impl From<[u8; REGISTER_FILE_LEN]> for RegisterSnapshot {
+    fn from(value: [u8; REGISTER_FILE_LEN]) -> Self {
+        let [iodira, iodirb, ipola, ipolb, gpintena, gpintenb, defvala, defvalb, intcona, intconb, iocona, ioconb, gppua, gppub, intfa, intfb, intcapa, intcapb, gpioa, gpiob, olata, olatb] =
+            value;
+        RegisterSnapshot {
+            iodira,
+            iodirb,
+            ipola,
+            ipolb,
+            gpintena,
+            gpintenb,
+            defvala,
+            defvalb,
+            intcona,
+            intconb,
+            iocona,
+            ioconb,
+            gppua,
+            gppub,
+            intfa,
+            intfb,
+            intcapa,
+            intcapb,
+            gpioa,
+            gpiob,
+            olata,
+            olatb,
         }
     }
 }
 
+impl From<RegisterSnapshot> for [u8; REGISTER_FILE_LEN] {
+    fn from(value: RegisterSnapshot) -> Self {
+        [
+            value.iodira,
+            value.iodirb,
+            value.ipola,
+            value.ipolb,
+            value.gpintena,
+            value.gpintenb,
+            value.defvala,
+            value.defvalb,
+            value.intcona,
+            value.intconb,
+            value.iocona,
+            value.ioconb,
+            value.gppua,
+            value.gppub,
+            value.intfa,
+            value.intfb,
+            value.intcapa,
+            value.intcapb,
+            value.gpioa,
+            value.gpiob,
+            value.olata,
+            value.olatb,
+        ]
+    }
+}
+
 #[maybe_async_cfg::maybe(
-    sync(cfg(not(feature = "async")), self = "MCP23017",),
+    sync(cfg(not(feature = "async")), keep_self,),
     async(feature = "async", keep_self)
 )]
-impl<I2C, E> MCP23017<I2C, InputReady>
+trait RegReadWrite<E> {
+    async fn write_config(&mut self, register: Register, value: u16) -> Result<(), Error<E>>;
+    async fn read_config(&mut self, register: Register) -> Result<u16, Error<E>>;
+    async fn read_registers(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error<E>>;
+}
+
+impl<I2C, E, State> MCP23017<I2C, State>
 where
     I2C: I2c<Error = E>,
 {
     /**
-     * Function used to read the input
+     * Function used to create a new handler for chip/port/pin
      */
     #[inline]
-    pub async fn read(&mut self) -> Result<u16, Error> {
-        let mut reg = self
-            .read_config(Register::Gpio)
-            .await
-            .map_err(i2c_comm_error)?;
-        Ok(reg)
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        MCP23017 {
+            i2c,
+            address,
+            read_style: Default::default(),
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: Default::default(),
+            write_corrections: 0,
+            output_cache: None,
+            state: Default::default(),
+        }
     }
 
     /**
-     * Function used to read the input pin
+     * Function used to choose how register reads are issued on the bus
      */
     #[inline]
-    pub async fn read_pin(&mut self, port: MyPort, pin: PinNumber) -> Result<u8, Error> {
-        let mut result = self.read().await?.to_le_bytes();
-
-        let result = match port {
-            MyPort::Porta => bit_read(result[0], pin),
-            MyPort::Portb => bit_read(result[1], pin),
-        };
-
-        Ok(result)
+    pub fn set_read_style(&mut self, read_style: ReadStyle) {
+        self.read_style = read_style;
     }
 
     /**
-     * Function used to disable the interrupt on the input
+     * Function used to choose whether output writes are read back and rewritten on mismatch
      */
     #[inline]
-    pub async fn disable_interrupt(&mut self, port: MyPort, pin: PinNumber) -> Result<(), Error> {
-        let mut reg = self.read_config(Register::Gpinten).await?.to_le_bytes();
-
-        match port {
-            MyPort::Porta => reg[0] = bit_clear(reg[0], pin),
-            MyPort::Portb => reg[1] = bit_clear(reg[1], pin),
-        };
-        let reg = LittleEndian::read_u16(&reg);
-
-        self.write_config(Register::Gpinten, reg).await
+    pub fn set_post_write_verify(&mut self, post_write_verify: PostWriteVerify) {
+        self.post_write_verify = post_write_verify;
     }
 
     /**
-     * Function used to enable the interrupt on the input
+     * Function used to get how many output writes were corrected by the post write verify
      */
     #[inline]
-    pub async fn enable_interrupt(&mut self, port: MyPort, pin: PinNumber) -> Result<(), Error> {
-        let mut reg = self.read_config(Register::Gpinten).await?.to_le_bytes();
-
-        match port {
-            MyPort::Porta => reg[0] = bit_set(reg[0], pin),
-            MyPort::Portb => reg[1] = bit_set(reg[1], pin),
-        };
-
-        let reg = LittleEndian::read_u16(&reg);
-        self.write_config(Register::Gpinten, reg).await
+    pub fn write_corrections(&self) -> u32 {
+        self.write_corrections
     }
 
     /**
-     * Function used to verify the interrupt on the input
+     * Function used to verify if the output cache is enabled
      */
     #[inline]
-    pub async fn get_interrupted_pin(&mut self, port: MyPort) -> Option<PinNumber> {
-        let pin_msk = self
-            .read_config(Register::Intf)
-            .await
-            .unwrap_or(0)
-            .to_le_bytes();
-
-        let result = match port {
-            MyPort::Porta => {
-                if pin_msk[0] != 0 {
-                    pin_msk[0]
-                } else {
-                    0
-                }
-            }
-            MyPort::Portb => {
-                if pin_msk[1] != 0 {
-                    pin_msk[1]
-                } else {
-                    0
-                }
-            }
-        };
+    pub fn output_cache_enabled(&self) -> bool {
+        self.output_cache.is_some()
+    }
 
-        pin_mask_to_number(PinMask::from(result))
+    /**
+     * Function used to turn the output cache off, write_pin reads GPIO again
+     */
+    #[inline]
+    pub fn disable_output_cache(&mut self) {
+        self.output_cache = None;
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /**
+     * Private function used to keep the output cache (if enabled) in step with a word
+     * written to the output latch
+     */
+    #[inline]
+    fn update_output_cache(&mut self, latch: u16) {
+        if self.output_cache.is_some() {
+            self.output_cache = Some(latch ^ self.output_inversion);
+        }
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), self = "MCP23017",),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, E, State> RegReadWrite<E> for MCP23017<I2C, State>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Private function used to read the chip registers using i2c
+     */
+    #[inline]
+    async fn read_config(&mut self, register: Register) -> Result<u16, Error<E>> {
+        let mut rx_buffer: [u8; 2] = [0; 2];
+        self.read_registers(register as u8, &mut rx_buffer).await?;
+        Ok(join_ports(rx_buffer))
+    }
+
+    /**
+     * Private function used to read consecutive register bytes using i2c
+     */
+    #[inline]
+    async fn read_registers(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        match self.read_style {
+            ReadStyle::RepeatedStart => self
+                .i2c
+                .write_read(self.address, &[address], buffer)
+                .await
+                .map_err(i2c_comm_error)?,
+            ReadStyle::StopThenRead => {
+                self.i2c
+                    .write(self.address, &[address])
+                    .await
+                    .map_err(i2c_comm_error)?;
+                self.i2c
+                    .read(self.address, buffer)
+                    .await
+                    .map_err(i2c_comm_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Private function used to write the chip registers using i2c
+     */
+    #[inline]
+    async fn write_config(&mut self, register: Register, value: u16) -> Result<(), Error<E>> {
+        let register_address = register as u8;
+        let [porta_value, portb_value] = split_ports(value);
+        #[cfg(feature = "strict")]
+        {
+            check_reserved_bits(register, porta_value)?;
+            check_reserved_bits(register, portb_value)?;
+        }
+        self.i2c
+            .write(self.address, &[register_address, porta_value, portb_value])
+            .await
+            .map_err(i2c_comm_error)?;
+        Ok(())
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), self = "MCP23017",),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, E, State> MCP23017<I2C, State>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function used to read the pin directions as a typed register pair
+     */
+    #[inline]
+    pub async fn read_iodir(&mut self) -> Result<IodirPair, Error<E>> {
+        Ok(IodirPair(self.read_config(Register::Iodir).await?))
+    }
+
+    /**
+     * Function used to render the current pin states of a port, GPIO is read once per call
+     */
+    #[inline]
+    pub async fn format_port<W: core::fmt::Write>(
+        &mut self,
+        port: MyPort,
+        w: &mut W,
+        style: FormatStyle,
+    ) -> Result<(), Error<E>> {
+        let gpio = self.read_config(Register::Gpio).await?;
+
+        format_byte(port_byte(gpio, port), port, style, w)
+            .map_err(|_| Error::Protocol(ProtocolError::FormatFailed))
+    }
+
+    /**
+     * Function used to choose the address pointer behaviour (IOCON.SEQOP), the driver
+     * works in both modes but the burst operations need Sequential
+     */
+    #[inline]
+    pub async fn set_address_mode(&mut self, mode: AddressMode) -> Result<(), Error<E>> {
+        let reg = self.read_config(Register::Iocon).await?;
+
+        let mut regres = split_ports(reg);
+        for byte in regres.iter_mut() {
+            match mode {
+                AddressMode::Sequential => *byte &= !IOCON_SEQOP,
+                AddressMode::Byte => *byte |= IOCON_SEQOP,
+            }
+        }
+
+        self.write_config(Register::Iocon, join_ports(regres)).await
+    }
+
+    /**
+     * Function used to read the whole register file (IODIRA to OLATB) in a single burst,
+     * needs Sequential address mode. Reading INTCAP clears a pending interrupt
+     */
+    #[inline]
+    pub async fn read_all_registers(&mut self) -> Result<[u8; REGISTER_FILE_LEN], Error<E>> {
+        let mut registers = [0; REGISTER_FILE_LEN];
+        self.read_registers(Register::Iodir as u8, &mut registers)
+            .await?;
+        Ok(registers)
+    }
+
+    /**
+     * Function used to write consecutive register bytes in a single burst starting at the
+     * given register, needs Sequential address mode. Fails with InvalidParameter if the
+     * data runs past the end of the register file. The output cache is not updated
+     */
+    #[inline]
+    pub async fn write_multiple(&mut self, start: Register, data: &[u8]) -> Result<(), Error<E>> {
+        let start = start as usize;
+        if data.is_empty() || start + data.len() > REGISTER_FILE_LEN {
+            return Err(Error::Protocol(ProtocolError::InvalidParameter));
+        }
+
+        let mut tx_buffer = [0; REGISTER_FILE_LEN + 1];
+        tx_buffer[0] = start as u8;
+        tx_buffer[1..=data.len()].copy_from_slice(data);
+        #[cfg(feature = "strict")]
+        for (offset, byte) in data.iter().enumerate() {
+            check_reserved_bits(Register::ALL[(start + offset) / 2], *byte)?;
+        }
+
+        self.i2c
+            .write(self.address, &tx_buffer[..=data.len()])
+            .await
+            .map_err(i2c_comm_error)
+    }
+
+    /**
+     * Function used to apply a complete configuration in one sequential burst, needs
+     * Sequential address mode. IOCON.SEQOP is set by a second write once the burst is
+     * done, IOCON.BANK is rejected with InvalidParameter since the driver works in bank 0
+     */
+    #[inline]
+    pub async fn apply_config(&mut self, config: &ConfigBuilder) -> Result<(), Error<E>> {
+        if config.iocon & IOCON_BANK != 0 {
+            return Err(Error::Protocol(ProtocolError::InvalidParameter));
+        }
+
+        let burst_iocon = config.iocon & !IOCON_SEQOP;
+        let mut data = [0; Register::Gppu as usize + 2];
+        for (register, value) in [
+            (Register::Iodir, config.iodir),
+            (Register::Ipol, config.ipol),
+            (Register::Gpinten, config.gpinten),
+            (Register::Defval, config.defval),
+            (Register::Intcon, config.intcon),
+            (Register::Iocon, join_ports([burst_iocon, burst_iocon])),
+            (Register::Gppu, config.gppu),
+        ] {
+            let address = register as usize;
+            data[address..address + 2].copy_from_slice(&split_ports(value));
+        }
+        self.write_multiple(Register::Iodir, &data).await?;
+
+        if config.iocon != burst_iocon {
+            self.write_config(Register::Iocon, join_ports([config.iocon, config.iocon]))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Function used to find the register layout the chip is in by probing IOCON in both
+     * layouts. The driver addresses the chip in bank 0, when bank 1 is found and rewrite
+     * is set IOCON.BANK is cleared to bring the chip back. Returns the layout found before
+     * any rewrite, or UnknownAddressing (and writes nothing) if IOCON is not found in either
+     * layout or the probe matches both
+     */
+    #[inline]
+    pub async fn recover_addressing(&mut self, rewrite: bool) -> Result<Bank, Error<E>> {
+        let mut bank1 = [0; 2];
+        self.read_registers(IOCON_BANK1_PORTA, &mut bank1[0..1])
+            .await?;
+        self.read_registers(IOCON_BANK1_PORTB, &mut bank1[1..2])
+            .await?;
+        let mut bank0 = [0; 2];
+        self.read_registers(Register::Iocon as u8, &mut bank0)
+            .await?;
+
+        match bank_from_iocon(bank1, bank0) {
+            Some(Bank::Bank0) => return Ok(Bank::Bank0),
+            Some(Bank::Bank1) => {}
+            None => return Err(Error::Protocol(ProtocolError::UnknownAddressing)),
+        }
+
+        if rewrite {
+            self.i2c
+                .write(self.address, &[IOCON_BANK1_PORTA, bank1[0] & !IOCON_BANK])
+                .await
+                .map_err(i2c_comm_error)?;
+        }
+        Ok(Bank::Bank1)
+    }
+
+    /**
+     * Function used to save the whole register file (e.g. before power gating), needs
+     * Sequential address mode. Reading INTCAP clears a pending interrupt
+     */
+    #[inline]
+    pub async fn dump_registers(&mut self) -> Result<RegisterSnapshot, Error<E>> {
+        Ok(RegisterSnapshot::from(self.read_all_registers().await?))
+    }
+
+    /**
+     * Function used to restore a saved register file, the output latch is written first so
+     * outputs come up at their saved level, then the configuration is applied as with
+     * apply_config. INTF, INTCAP and GPIO are not writable and are skipped
+     */
+    #[inline]
+    pub async fn restore_registers(&mut self, snapshot: &RegisterSnapshot) -> Result<(), Error<E>> {
+        if snapshot.iocona != snapshot.ioconb {
+            return Err(Error::Protocol(ProtocolError::InvalidParameter));
+        }
+
+        let olat = join_ports([snapshot.olata, snapshot.olatb]);
+        self.write_config(Register::Olat, olat).await?;
+        self.update_output_cache(olat);
+
+        let config = ConfigBuilder::new()
+            .with_iodir(join_ports([snapshot.iodira, snapshot.iodirb]))
+            .with_ipol(join_ports([snapshot.ipola, snapshot.ipolb]))
+            .with_gpinten(join_ports([snapshot.gpintena, snapshot.gpintenb]))
+            .with_defval(join_ports([snapshot.defvala, snapshot.defvalb]))
+            .with_intcon(join_ports([snapshot.intcona, snapshot.intconb]))
+            .with_iocon(snapshot.iocona)
+            .with_gppu(join_ports([snapshot.gppua, snapshot.gppub]));
+        self.apply_config(&config).await
+    }
+
+    /**
+     * Function used to read the chip configuration and look for suspicious settings
+     */
+    #[inline]
+    pub async fn lint_config(&mut self) -> Result<ConfigLint, Error<E>> {
+        Ok(ConfigLint {
+            iodir: self.read_config(Register::Iodir).await?,
+            gpinten: self.read_config(Register::Gpinten).await?,
+            intcon: self.read_config(Register::Intcon).await?,
+            defval: self.read_config(Register::Defval).await?,
+            gppu: self.read_config(Register::Gppu).await?,
+            index: 0,
+        })
+    }
+}
+
+#[allow(dead_code)]
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), self = "MCP23017",),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, E> MCP23017<I2C, Configuring>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function used to create a new handler once the device answers, the probe read
+     * is retried every millisecond until timeout_ms expires
+     */
+    #[inline]
+    pub async fn new_wait_ready<D: DelayNs>(
+        i2c: I2C,
+        address: u8,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<Self, Error<E>> {
+        let mut mcp = Self::new(i2c, address);
+        let mut elapsed_ms = 0;
+
+        loop {
+            match mcp.read_config(Register::Iocon).await {
+                Ok(_) => return Ok(mcp),
+                Err(e) if elapsed_ms >= timeout_ms => return Err(e),
+                Err(_) => {}
+            }
+            delay.delay_ms(1).await;
+            elapsed_ms += 1;
+        }
+    }
+
+    /**
+     * Function used to set the chip/port/pin as input
+     */
+    #[inline]
+    pub async fn set_as_input(mut self) -> Result<MCP23017<I2C, InputConfiguring>, Error<E>> {
+        self.write_config(Register::Iodir, 0xFFFF).await?;
+
+        Ok(MCP23017 {
+            i2c: self.i2c,
+            address: self.address,
+            read_style: self.read_style,
+            spurious_interrupts: self.spurious_interrupts,
+            output_inversion: self.output_inversion,
+            staged_interrupts: self.staged_interrupts,
+            post_write_verify: self.post_write_verify,
+            write_corrections: self.write_corrections,
+            output_cache: self.output_cache,
+            state: core::marker::PhantomData::<InputConfiguring>,
+        })
+    }
+
+    /**
+     * Function used to set the chip/port/pin as output
+     */
+    #[inline]
+    pub async fn set_as_output(mut self) -> Result<MCP23017<I2C, OutputReady>, Error<E>> {
+        self.write_config(Register::Iodir, 0x0000).await?;
+
+        Ok(MCP23017 {
+            i2c: self.i2c,
+            address: self.address,
+            read_style: self.read_style,
+            spurious_interrupts: self.spurious_interrupts,
+            output_inversion: self.output_inversion,
+            staged_interrupts: self.staged_interrupts,
+            post_write_verify: self.post_write_verify,
+            write_corrections: self.write_corrections,
+            output_cache: self.output_cache,
+            state: core::marker::PhantomData::<OutputReady>,
+        })
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), self = "MCP23017",),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, E> MCP23017<I2C, OutputReady>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function used to set which output pins of a port are active-low, the logical values
+     * passed to the write functions are inverted on those pins before reaching the chip
+     */
+    #[inline]
+    pub fn set_output_inversion_mask(&mut self, port: MyPort, mask: u8) {
+        let inversion = set_port_byte(self.output_inversion, port, mask);
+        if let Some(cache) = self.output_cache {
+            self.output_cache = Some(cache ^ self.output_inversion ^ inversion);
+        }
+        self.output_inversion = inversion;
+    }
+
+    /**
+     * Function used to get the output inversion mask of a port
+     */
+    #[inline]
+    pub fn output_inversion_mask(&self, port: MyPort) -> u8 {
+        port_byte(self.output_inversion, port)
+    }
+
+    /**
+     * Function used to write the output value to be set on chip/port/pin, the latch is read
+     * back and rewritten when post write verify is enabled
+     */
+    #[inline]
+    pub async fn write(&mut self, value: impl Into<GpioPair>) -> Result<(), Error<E>> {
+        self.write_output(Register::Gpio, value.into().into()).await
+    }
+
+    /**
+     * Function used to write the output value straight to the output latch (OLAT), the
+     * latch is read back and rewritten when post write verify is enabled
+     */
+    #[inline]
+    pub async fn write_output_latch(&mut self, value: impl Into<OlatPair>) -> Result<(), Error<E>> {
+        self.write_output(Register::Olat, value.into().into()).await
+    }
+
+    /**
+     * Function used to read the output latch (OLAT) as logical values (output inversion
+     * applied), unlike GPIO it is not affected by external loading of the pins
+     */
+    #[inline]
+    pub async fn read_output_latch(&mut self) -> Result<OlatPair, Error<E>> {
+        Ok(OlatPair(
+            self.read_config(Register::Olat).await? ^ self.output_inversion,
+        ))
+    }
+
+    /**
+     * Function used to write the output value of a pin with a read-modify-write of the
+     * output latch (OLAT), so externally pulled pins are not copied into the latch
+     */
+    #[inline]
+    pub async fn write_pin_latch(
+        &mut self,
+        port: MyPort,
+        pin: PinNumber,
+        value: PinSet,
+    ) -> Result<(), Error<E>> {
+        let result = self.read_output_latch().await?;
+
+        self.write_output_latch(result.with_pin(port, pin, value))
+            .await
+    }
+
+    /**
+     * Function used to drive the pins of set_mask high and the pins of clear_mask low with
+     * a single latch read (skipped when the output cache is enabled) and write, pins in both
+     * masks are set
+     */
+    #[inline]
+    pub async fn modify_pins(
+        &mut self,
+        port: MyPort,
+        set_mask: u8,
+        clear_mask: u8,
+    ) -> Result<(), Error<E>> {
+        let result = self.output_word().await?;
+        let byte = (result.port(port) & !clear_mask) | set_mask;
+
+        self.write_output_latch(result.with_port(port, byte)).await
+    }
+
+    /**
+     * Function used to invert the pins of mask with a single latch read (skipped when the
+     * output cache is enabled) and write
+     */
+    #[inline]
+    pub async fn toggle_pins(&mut self, port: MyPort, mask: u8) -> Result<(), Error<E>> {
+        let result = self.output_word().await?;
+        let byte = result.port(port) ^ mask;
+
+        self.write_output_latch(result.with_port(port, byte)).await
+    }
+
+    /**
+     * Function used to invert the output value of a pin
+     */
+    #[inline]
+    pub async fn toggle_pin(&mut self, port: MyPort, pin: PinNumber) -> Result<(), Error<E>> {
+        self.toggle_pins(port, pin_number_to_mask(pin) as u8).await
+    }
+
+    /**
+     * Private function used to get the logical output word from the output cache, or from
+     * the output latch when the cache is disabled
+     */
+    #[inline]
+    async fn output_word(&mut self) -> Result<OlatPair, Error<E>> {
+        match self.output_cache {
+            Some(cache) => Ok(OlatPair(cache)),
+            None => self.read_output_latch().await,
+        }
+    }
+
+    /**
+     * Private function used to write a logical output word to GPIO or OLAT, applying the
+     * output inversion and the post write verify
+     */
+    #[inline]
+    async fn write_output(&mut self, register: Register, value: u16) -> Result<(), Error<E>> {
+        let value = value ^ self.output_inversion;
+        self.write_config(register, value).await?;
+        self.update_output_cache(value);
+
+        if let PostWriteVerify::Retries(retries) = self.post_write_verify {
+            let mut attempt = 0;
+            while self.read_config(Register::Olat).await? != value {
+                if attempt == retries {
+                    return Err(Error::Protocol(ProtocolError::WriteVerifyFailed));
+                }
+                self.write_config(register, value).await?;
+                self.write_corrections = self.write_corrections.saturating_add(1);
+                attempt += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Function used to turn the output cache on, seeded from the output latch (OLAT).
+     * write_pin then modifies the cached word instead of reading GPIO first, so the
+     * chip must not be written through other handles while the cache is enabled
+     */
+    #[inline]
+    pub async fn enable_output_cache(&mut self) -> Result<(), Error<E>> {
+        let result = self.read_output_latch().await?;
+        self.output_cache = Some(result.into());
+        Ok(())
+    }
+
+    /**
+     * Function used to write the output value to be set on pin
+     */
+    #[inline]
+    pub async fn write_pin(
+        &mut self,
+        port: MyPort,
+        pin: PinNumber,
+        value: PinSet,
+    ) -> Result<(), Error<E>> {
+        let result = match self.output_cache {
+            Some(cache) => GpioPair(cache),
+            None => GpioPair(self.read_config(Register::Gpio).await? ^ self.output_inversion),
+        };
+
+        self.write(result.with_pin(port, pin, value)).await
+    }
+
+    /**
+     * Function used to write the output value to be set on the pin given by its datasheet name
+     */
+    #[inline]
+    pub async fn write_gpio(&mut self, name: GpioName, value: PinSet) -> Result<(), Error<E>> {
+        self.write_pin(name.port(), name.pin(), value).await
+    }
+
+    /**
+     * Function used to precompute the I2C writes of an output waveform, fails with
+     * InvalidParameter if there are more frames than the plan can hold
+     */
+    #[inline]
+    pub fn prepare_waveform<const N: usize>(
+        &self,
+        frames: &[u16],
+    ) -> Result<WaveformPlan<N>, Error<E>> {
+        if frames.len() > N {
+            return Err(Error::Protocol(ProtocolError::InvalidParameter));
+        }
+
+        let mut plan = WaveformPlan {
+            frames: [[0; 3]; N],
+            len: frames.len(),
+        };
+        for (encoded, frame) in plan.frames.iter_mut().zip(frames) {
+            let [porta_value, portb_value] = split_ports(*frame ^ self.output_inversion);
+            *encoded = [Register::Gpio as u8, porta_value, portb_value];
+        }
+
+        Ok(plan)
+    }
+
+    /**
+     * Function used to play a precomputed waveform, waiting period_us after every frame
+     */
+    #[inline]
+    pub async fn play<D: DelayNs, const N: usize>(
+        &mut self,
+        plan: &WaveformPlan<N>,
+        delay: &mut D,
+        period_us: u32,
+    ) -> Result<(), Error<E>> {
+        for frame in &plan.frames[..plan.len] {
+            self.i2c
+                .write(self.address, frame)
+                .await
+                .map_err(i2c_comm_error)?;
+            self.update_output_cache(join_ports([frame[1], frame[2]]));
+            delay.delay_us(period_us).await;
+        }
+        Ok(())
+    }
+
+    /**
+     * Function used to verify an output pin by comparing its latch (OLAT) with the pin level (GPIO)
+     */
+    #[inline]
+    pub async fn verify_pin(
+        &mut self,
+        port: MyPort,
+        pin: PinNumber,
+    ) -> Result<PinVerify, Error<E>> {
+        let iodir = self.read_config(Register::Iodir).await?;
+        let olat = self.read_config(Register::Olat).await?;
+        let gpio = self.read_config(Register::Gpio).await?;
+
+        Ok(classify_output(
+            port_byte(iodir, port),
+            port_byte(olat, port),
+            port_byte(gpio, port),
+            pin,
+        ))
+    }
+
+    /**
+     * Function used to apply an output transaction one port at a time, on a bus error the
+     * ports already written (the failing one included) are restored to their previous
+     * values. Only a failure to read the initial latch state is returned as an error
+     */
+    #[inline]
+    pub async fn apply_transaction(
+        &mut self,
+        transaction: &OutputTransaction,
+    ) -> Result<TransactionReport, Error<E>> {
+        let previous = self.read_config(Register::Olat).await?;
+        let target = (((previous ^ self.output_inversion) & !transaction.mask)
+            | (transaction.value & transaction.mask))
+            ^ self.output_inversion;
+
+        let previous_bytes = split_ports(previous);
+        let target_bytes = split_ports(target);
+        let mut current = previous_bytes;
+
+        for (index, port) in MyPort::ALL.into_iter().enumerate() {
+            if target_bytes[index] == previous_bytes[index] {
+                continue;
+            }
+            if self.write_latch(port, target_bytes[index]).await.is_ok() {
+                current[index] = target_bytes[index];
+                continue;
+            }
+
+            let mut outcome = TransactionOutcome::RolledBack;
+            for (rollback, port) in MyPort::ALL.into_iter().enumerate().take(index + 1) {
+                if target_bytes[rollback] == previous_bytes[rollback] {
+                    continue;
+                }
+                if self
+                    .write_latch(port, previous_bytes[rollback])
+                    .await
+                    .is_ok()
+                {
+                    current[rollback] = previous_bytes[rollback];
+                } else {
+                    outcome = TransactionOutcome::RollbackFailed;
+                }
+            }
+
+            self.update_output_cache(join_ports(current));
+            return Ok(TransactionReport {
+                outcome,
+                output: join_ports(current) ^ self.output_inversion,
+            });
+        }
+
+        self.update_output_cache(target);
+        Ok(TransactionReport {
+            outcome: TransactionOutcome::Committed,
+            output: target ^ self.output_inversion,
+        })
+    }
+
+    /**
+     * Private function used to write the output latch of a single port
+     */
+    #[inline]
+    async fn write_latch(&mut self, port: MyPort, value: u8) -> Result<(), Error<E>> {
+        self.i2c
+            .write(self.address, &[Register::Olat as u8 | port as u8, value])
+            .await
+            .map_err(i2c_comm_error)
+    }
+
+    /**
+     * Function used to run an output sequence, the pins of every step mask are read back
+     * and verified before its minimum dwell time is waited, values are logical (output
+     * inversion applied)
+     */
+    #[inline]
+    pub async fn run_sequence<D: DelayNs>(
+        &mut self,
+        steps: &[SequenceStep],
+        delay: &mut D,
+    ) -> Result<SequenceReport, Error<E>> {
+        let mut current = self.read_config(Register::Gpio).await? ^ self.output_inversion;
+        let mut crc = 0xFFFF;
+
+        for (index, step) in steps.iter().enumerate() {
+            let expected = (current & !step.mask) | (step.value & step.mask);
+            self.write(expected).await?;
+
+            let read_back = self.read_config(Register::Gpio).await? ^ self.output_inversion;
+            if (read_back ^ expected) & step.mask != 0 {
+                return Ok(SequenceReport {
+                    steps_applied: index,
+                    crc,
+                    outcome: SequenceOutcome::VerifyFailed {
+                        step: index,
+                        expected,
+                        read_back,
+                    },
+                });
+            }
+
+            for byte in split_ports(expected) {
+                crc = crc16_update(crc, byte);
+            }
+            current = expected;
+
+            delay.delay_us(step.min_dwell_us).await;
+        }
+
+        Ok(SequenceReport {
+            steps_applied: steps.len(),
+            crc,
+            outcome: SequenceOutcome::Completed,
+        })
+    }
+}
+
+/// Interrupt event decoded from INTF, level is the pin level captured in INTCAP
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InterruptEvent {
+    pub port: MyPort,
+    pub pin: PinNumber,
+    pub level: PinSet,
+}
+
+/// Interrupt dispatcher owning an input chip, every flagged pin is decoded on each INT
+/// notification and handed to the handler registered for that pin
+#[derive(Debug)]
+pub struct InterruptManager<I2C> {
+    mcp: MCP23017<I2C, InputReady>,
+    handlers: [Option<fn(InterruptEvent)>; 16],
+    stall_threshold: u32,
+    asserted_ticks: u32,
+}
+
+/// INT line found asserted for longer than the stall threshold without being serviced,
+/// holds the INTCAP and GPIO words read to recover
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ServiceStall {
+    pub asserted_ticks: u32,
+    pub intcap: u16,
+    pub gpio: u16,
+}
+
+impl<I2C> InterruptManager<I2C> {
+    /**
+     * Function used to create a dispatcher owning the chip, no handler is registered
+     */
+    #[inline]
+    pub fn new(mcp: MCP23017<I2C, InputReady>) -> Self {
+        InterruptManager {
+            mcp,
+            handlers: [None; 16],
+            stall_threshold: 0,
+            asserted_ticks: 0,
+        }
+    }
+
+    /**
+     * Function used to set after how many ticks with the INT line asserted and no service
+     * on_tick reports a stall, 0 disables the watchdog
+     */
+    #[inline]
+    pub fn set_stall_threshold(&mut self, ticks: u32) {
+        self.stall_threshold = ticks;
+        self.asserted_ticks = 0;
+    }
+
+    /**
+     * Function used to register the handler of a pin, returns the replaced one
+     */
+    #[inline]
+    pub fn register(
+        &mut self,
+        name: GpioName,
+        handler: fn(InterruptEvent),
+    ) -> Option<fn(InterruptEvent)> {
+        self.handlers[name as usize].replace(handler)
+    }
+
+    /**
+     * Function used to remove the handler of a pin, returns the removed one
+     */
+    #[inline]
+    pub fn unregister(&mut self, name: GpioName) -> Option<fn(InterruptEvent)> {
+        self.handlers[name as usize].take()
+    }
+
+    /**
+     * Function used to take the chip back from the dispatcher
+     */
+    #[inline]
+    pub fn release(self) -> MCP23017<I2C, InputReady> {
+        self.mcp
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), self = "InterruptManager",),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, E> InterruptManager<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function to be called when the MCU INT line fires, INTF and INTCAP are read once
+     * (clearing the interrupt) and the events of every flagged pin are dispatched to their
+     * handlers, all events are returned whether a handler is registered or not
+     */
+    #[inline]
+    pub async fn on_interrupt(&mut self) -> Result<heapless::Vec<InterruptEvent, 16>, Error<E>> {
+        let mut events = heapless::Vec::new();
+
+        // Capacity matches the pin count, push cannot fail
+        self.dispatch(|event| {
+            let _ = events.push(event);
+        })
+        .await?;
+
+        Ok(events)
+    }
+
+    /**
+     * Function to be called when the MCU INT line fires, same as on_interrupt but the events
+     * are stored in the caller provided slice, returns how many were stored. Events that do
+     * not fit are still dispatched to their handlers
+     */
+    #[inline]
+    pub async fn on_interrupt_into(
+        &mut self,
+        events: &mut [InterruptEvent],
+    ) -> Result<usize, Error<E>> {
+        let mut count = 0;
+
+        self.dispatch(|event| {
+            if let Some(slot) = events.get_mut(count) {
+                *slot = event;
+                count += 1;
+            }
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /**
+     * Private function used to read INTF and INTCAP once and hand the event of every flagged
+     * pin to its handler and to sink
+     */
+    #[inline]
+    async fn dispatch(&mut self, mut sink: impl FnMut(InterruptEvent)) -> Result<(), Error<E>> {
+        self.asserted_ticks = 0;
+
+        let intf = self.mcp.read_config(Register::Intf).await?;
+        if intf == 0 {
+            return Ok(());
+        }
+        let intcap = IntcapPair(self.mcp.read_config(Register::Intcap).await?);
+
+        for name in GpioName::ALL {
+            if intf & (1 << name as u16) == 0 {
+                continue;
+            }
+            let event = InterruptEvent {
+                port: name.port(),
+                pin: name.pin(),
+                level: intcap.pin(name.port(), name.pin()),
+            };
+            if let Some(handler) = self.handlers[name as usize] {
+                handler(event);
+            }
+            sink(event);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Function to be called on a periodic tick with the level of the MCU INT line (true if
+     * asserted). When the line stays asserted for the stall threshold without on_interrupt
+     * being called, INTCAP and GPIO are read to clear the interrupt and the stall is reported
+     */
+    #[inline]
+    pub async fn on_tick(&mut self, int_asserted: bool) -> Result<Option<ServiceStall>, Error<E>> {
+        if !int_asserted || self.stall_threshold == 0 {
+            self.asserted_ticks = 0;
+            return Ok(None);
+        }
+
+        self.asserted_ticks = self.asserted_ticks.saturating_add(1);
+        if self.asserted_ticks < self.stall_threshold {
+            return Ok(None);
+        }
+
+        let asserted_ticks = self.asserted_ticks;
+        self.asserted_ticks = 0;
+        Ok(Some(ServiceStall {
+            asserted_ticks,
+            intcap: self.mcp.read_config(Register::Intcap).await?,
+            gpio: self.mcp.read_config(Register::Gpio).await?,
+        }))
+    }
+}
+
+/// Output handler that drives a safe output word and disables interrupts when dropped
+#[cfg(not(feature = "async"))]
+#[derive(Debug)]
+pub struct SafeOnDrop<I2C>
+where
+    I2C: I2c,
+{
+    mcp: MCP23017<I2C, OutputReady>,
+    safe_value: u16,
+}
+
+#[cfg(not(feature = "async"))]
+impl<I2C> MCP23017<I2C, OutputReady>
+where
+    I2C: I2c,
+{
+    /**
+     * Function used to wrap the output so safe_value (logical) is written when it is dropped
+     */
+    #[inline]
+    pub fn safe_on_drop(self, safe_value: u16) -> SafeOnDrop<I2C> {
+        SafeOnDrop {
+            mcp: self,
+            safe_value,
+        }
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<I2C> core::ops::Deref for SafeOnDrop<I2C>
+where
+    I2C: I2c,
+{
+    type Target = MCP23017<I2C, OutputReady>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.mcp
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<I2C> core::ops::DerefMut for SafeOnDrop<I2C>
+where
+    I2C: I2c,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.mcp
+    }
+}
+
+/**
+ * Function implements the Drop trait into SafeOnDrop, bus errors are ignored
+ */
+#[cfg(not(feature = "async"))]
+impl<I2C> Drop for SafeOnDrop<I2C>
+where
+    I2C: I2c,
+{
+    fn drop(&mut self) {
+        let _ = self.mcp.write(self.safe_value);
+        let _ = self.mcp.write_config(Register::Gpinten, 0x0000);
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), self = "MCP23017",),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, E> MCP23017<I2C, InputConfiguring>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function used to set the pull on the input
+     */
+    #[inline]
+    pub async fn set_pull(mut self, pull: PinSet) -> Result<Self, Error<E>> {
+        let result = match pull {
+            PinSet::High => 0xFFFF,
+            PinSet::Low => 0x0000,
+        };
+
+        self.write_config(Register::Gppu, result).await?;
+
+        Ok(self)
+    }
+
+    /**
+     * Function used to set the interrupt mirror function on the input
+     */
+    #[inline]
+    pub async fn set_interrupt_mirror(mut self, mirror: InterruptMirror) -> Result<Self, Error<E>> {
+        let mut reg = self.read_config(Register::Iocon).await?;
+
+        let mut regres = split_ports(reg);
+        match mirror {
+            InterruptMirror::MirrorOn => {
+                regres[0] |= InterruptMirror::MirrorOn as u8;
+                regres[1] |= InterruptMirror::MirrorOn as u8;
+            }
+            InterruptMirror::MirrorOff => {
+                regres[0] &= !(InterruptMirror::MirrorOn as u8);
+                regres[1] &= !(InterruptMirror::MirrorOn as u8);
+            }
+        }
+        reg = join_ports(regres);
+
+        self.write_config(Register::Iocon, reg).await?;
+
+        Ok(self)
+    }
+
+    /**
+     * Function used to set the output type of the INT pins (open-drain or push-pull with
+     * its active level)
+     */
+    #[inline]
+    pub async fn set_interrupt_output(mut self, output: InterruptOutput) -> Result<Self, Error<E>> {
+        let reg = self.read_config(Register::Iocon).await?;
+
+        let mut regres = split_ports(reg);
+        regres[0] = iocon_interrupt_output(regres[0], output);
+        regres[1] = iocon_interrupt_output(regres[1], output);
+
+        self.write_config(Register::Iocon, join_ports(regres))
+            .await?;
+
+        Ok(self)
+    }
+
+    /**
+     * Function used to set the polarity (IPOL) of an input pin
+     */
+    #[inline]
+    pub async fn set_polarity(
+        mut self,
+        port: MyPort,
+        pin: PinNumber,
+        polarity: Polarity,
+    ) -> Result<Self, Error<E>> {
+        let reg = self.read_config(Register::Ipol).await?;
+
+        let value = match polarity {
+            Polarity::Normal => PinSet::Low,
+            Polarity::Inverted => PinSet::High,
+        };
+        let reg = IpolPair(reg).with_pin(port, pin, value);
+
+        self.write_config(Register::Ipol, reg.into()).await?;
+        Ok(self)
+    }
+
+    /**
+     * Function used to choose the pin as interrupt on the input
+     */
+    #[inline]
+    pub async fn set_interrupt_on(
+        mut self,
+        port: MyPort,
+        pin: PinNumber,
+        interrupt_on: InterruptOn,
+    ) -> Result<Self, Error<E>> {
+        let reg = IntconPair(self.read_config(Register::Intcon).await?);
+
+        let reg = match interrupt_on {
+            InterruptOn::PinChange => reg.with_pin(port, pin, PinSet::Low),
+            InterruptOn::ChangeFromRegister => reg.with_pin(port, pin, PinSet::High),
+        };
+
+        self.write_config(Register::Intcon, reg.into()).await?;
+        Ok(self)
+    }
+
+    /**
+     * Function used to set the interrupt compare function on the input
+     */
+    #[inline]
+    pub async fn set_interrupt_compare(
+        mut self,
+        port: MyPort,
+        pin: PinNumber,
+        value: PinSet,
+    ) -> Result<Self, Error<E>> {
+        let intcon = IntconPair(self.read_config(Register::Intcon).await?);
+
+        if intcon.pin(port, pin) != PinSet::High {
+            return Err(Error::Protocol(ProtocolError::InvalidInterruptSetting));
+        }
+
+        let reg = DefvalPair(self.read_config(Register::Defval).await?); //change only valid if intcon is set to 1
+
+        self.write_config(Register::Defval, reg.with_pin(port, pin, value).into())
+            .await?;
+        Ok(self)
+    }
+
+    /**
+     * Function used to stage the interrupt of an input pin, nothing is written to the chip
+     * until arm() is called on the ready input so no event fires while half configured
+     */
+    #[inline]
+    pub fn stage_interrupt(mut self, port: MyPort, pin: PinNumber) -> Self {
+        self.staged_interrupts = GpintenPair(self.staged_interrupts)
+            .with_pin(port, pin, PinSet::High)
+            .into();
+        self
+    }
+
+    /**
+     * Function used to set input to the ready state
+     */
+    #[inline]
+    pub fn ready(mut self) -> MCP23017<I2C, InputReady> {
+        MCP23017 {
+            i2c: self.i2c,
+            address: self.address,
+            read_style: self.read_style,
+            spurious_interrupts: self.spurious_interrupts,
+            output_inversion: self.output_inversion,
+            staged_interrupts: self.staged_interrupts,
+            post_write_verify: self.post_write_verify,
+            write_corrections: self.write_corrections,
+            output_cache: self.output_cache,
+            state: core::marker::PhantomData::<InputReady>,
+        }
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), self = "MCP23017",),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, E> MCP23017<I2C, InputReady>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function used to read the input
+     */
+    #[inline]
+    pub async fn read(&mut self) -> Result<u16, Error<E>> {
+        let mut reg = self.read_config(Register::Gpio).await?;
+        Ok(reg)
+    }
+
+    /**
+     * Function used to read the input as a typed register pair
+     */
+    #[inline]
+    pub async fn read_gpio(&mut self) -> Result<GpioPair, Error<E>> {
+        Ok(GpioPair(self.read().await?))
+    }
+
+    /**
+     * Function used to read the input pin
+     */
+    #[inline]
+    pub async fn read_pin(&mut self, port: MyPort, pin: PinNumber) -> Result<u8, Error<E>> {
+        let result = self.read().await?;
+
+        Ok(GpioPair(result).pin(port, pin) as u8)
+    }
+
+    /**
+     * Function used to read the input pin as a pin level
+     */
+    #[inline]
+    pub async fn read_pin_level(
+        &mut self,
+        port: MyPort,
+        pin: PinNumber,
+    ) -> Result<PinSet, Error<E>> {
+        let result = self.read().await?;
+
+        Ok(GpioPair(result).pin(port, pin))
+    }
+
+    /**
+     * Function used to read the level of the input pin given by its datasheet name
+     */
+    #[inline]
+    pub async fn read_gpio_level(&mut self, name: GpioName) -> Result<PinSet, Error<E>> {
+        self.read_pin_level(name.port(), name.pin()).await
+    }
+
+    /**
+     * Function used to sample an input pin every period_us until the sampler window is
+     * complete, returns the duty cycle reported by the sampler. Each sample is a single
+     * GPIO burst read of both ports
+     */
+    #[inline]
+    pub async fn sample_duty<D: DelayNs>(
+        &mut self,
+        port: MyPort,
+        pin: PinNumber,
+        sampler: &mut DutySampler,
+        period_us: u32,
+        delay: &mut D,
+    ) -> Result<u16, Error<E>> {
+        sampler.reset();
+        loop {
+            let level = GpioPair(self.read().await?).pin(port, pin);
+            if let Some(duty) = sampler.observe(level) {
+                return Ok(duty);
+            }
+            delay.delay_us(period_us).await;
+        }
+    }
+
+    /**
+     * Function used to disable the interrupt on the input, GPINTEN is written right away
+     * and the pin is removed from the staged interrupts so the next arm() keeps it disabled
+     */
+    #[inline]
+    pub async fn disable_interrupt(
+        &mut self,
+        port: MyPort,
+        pin: PinNumber,
+    ) -> Result<(), Error<E>> {
+        let reg = self.read_config(Register::Gpinten).await?;
+
+        let reg = GpintenPair(reg).with_pin(port, pin, PinSet::Low).into();
+
+        self.write_config(Register::Gpinten, reg).await?;
+        self.staged_interrupts = GpintenPair(self.staged_interrupts)
+            .with_pin(port, pin, PinSet::Low)
+            .into();
+        Ok(())
+    }
+
+    /**
+     * Function used to enable the interrupt on the input, GPINTEN is written right away
+     * and the pin is added to the staged interrupts so the next arm() keeps it enabled
+     */
+    #[inline]
+    pub async fn enable_interrupt(&mut self, port: MyPort, pin: PinNumber) -> Result<(), Error<E>> {
+        let reg = self.read_config(Register::Gpinten).await?;
+
+        let reg = GpintenPair(reg).with_pin(port, pin, PinSet::High).into();
+        self.write_config(Register::Gpinten, reg).await?;
+        self.staged_interrupts = GpintenPair(self.staged_interrupts)
+            .with_pin(port, pin, PinSet::High)
+            .into();
+        Ok(())
+    }
+
+    /**
+     * Function used to activate the staged interrupts, GPINTEN is written in a single write
+     */
+    #[inline]
+    pub async fn arm(&mut self) -> Result<(), Error<E>> {
+        self.write_config(Register::Gpinten, self.staged_interrupts)
+            .await
+    }
+
+    /**
+     * Function used to deactivate all interrupts in a single write, the staged interrupts
+     * are kept so arm() can activate them again
+     */
+    #[inline]
+    pub async fn disarm(&mut self) -> Result<(), Error<E>> {
+        self.write_config(Register::Gpinten, 0x0000).await
+    }
+
+    /**
+     * Function used to get the interrupts staged for arm()
+     */
+    #[inline]
+    pub fn staged_interrupts(&self) -> u16 {
+        self.staged_interrupts
+    }
+
+    /**
+     * Function used to diagnose an interrupt that fired with no flag set in INTF for the port,
+     * the capture is read to clear the interrupt. Returns None if the interrupt is legitimate
+     */
+    #[inline]
+    pub async fn diagnose_spurious_interrupt(
+        &mut self,
+        port: MyPort,
+    ) -> Result<Option<SpuriousInterrupt>, Error<E>> {
+        let intf = self.read_config(Register::Intf).await?;
+        if port_byte(intf, port) != 0 {
+            return Ok(None);
+        }
+        self.spurious_interrupts = self.spurious_interrupts.saturating_add(1);
+
+        let iocon = self.read_config(Register::Iocon).await?;
+        let intcap = self.read_config(Register::Intcap).await?;
+        let gpio = self.read_config(Register::Gpio).await?;
+
+        let mirrored = port_byte(iocon, port) & InterruptMirror::MirrorOn as u8 != 0;
+        let result = if mirrored && intf != 0 {
+            SpuriousInterrupt::MirrorMisconfig
+        } else if port_byte(intcap, port) != port_byte(gpio, port) {
+            SpuriousInterrupt::ClearedByRaceRead
+        } else {
+            SpuriousInterrupt::NoiseOnIntLine
+        };
+
+        Ok(Some(result))
+    }
+
+    /**
+     * Function used to get how many spurious interrupts were diagnosed
+     */
+    #[inline]
+    pub fn spurious_interrupt_count(&self) -> u32 {
+        self.spurious_interrupts
+    }
+
+    /**
+     * Function used to read the pin levels latched (INTCAP) when the last interrupt fired,
+     * reading the capture clears the interrupt
+     */
+    #[inline]
+    pub async fn read_interrupt_capture(&mut self) -> Result<u16, Error<E>> {
+        self.read_config(Register::Intcap).await
+    }
+
+    /**
+     * Function used to read the pin levels of a port latched (INTCAP) when the last interrupt fired
+     */
+    #[inline]
+    pub async fn get_interrupt_capture(&mut self, port: MyPort) -> Result<u8, Error<E>> {
+        let intcap = self.read_interrupt_capture().await?;
+
+        Ok(port_byte(intcap, port))
+    }
+
+    /**
+     * Function used to get the pin that fired the interrupt on a port together with its
+     * level at interrupt time, the lowest pin is returned when several are flagged (see
+     * get_interrupted_pins), returns None if no pin of the port is flagged
+     */
+    #[inline]
+    pub async fn get_interrupted_pin_level(
+        &mut self,
+        port: MyPort,
+    ) -> Result<Option<(PinNumber, PinSet)>, Error<E>> {
+        let intf = self.read_config(Register::Intf).await?;
+        let Some(pin) = PinFlags(port_byte(intf, port)).next() else {
+            return Ok(None);
+        };
+        let intcap = self.read_interrupt_capture().await?;
+
+        Ok(Some((pin, IntcapPair(intcap).pin(port, pin))))
+    }
+
+    /**
+     * Function used to get every pin of a port flagged in INTF
+     */
+    #[inline]
+    pub async fn get_interrupted_pins(&mut self, port: MyPort) -> Result<PinFlags, Error<E>> {
+        let intf = self.read_config(Register::Intf).await?;
+
+        Ok(PinFlags(port_byte(intf, port)))
+    }
+
+    /**
+     * Function used to verify the interrupt on the input, communication errors are returned
+     * instead of being reported as no interrupt
+     */
+    #[inline]
+    pub async fn try_get_interrupted_pin(
+        &mut self,
+        port: MyPort,
+    ) -> Result<Option<PinNumber>, Error<E>> {
+        let pin_msk = self.read_config(Register::Intf).await?;
+
+        Ok(pin_mask_to_number(PinMask::from(port_byte(pin_msk, port))))
+    }
+
+    /**
+     * Function used to verify the interrupt on the input, a communication error is reported
+     * as None (see try_get_interrupted_pin)
+     */
+    #[inline]
+    pub async fn get_interrupted_pin(&mut self, port: MyPort) -> Option<PinNumber> {
+        self.try_get_interrupted_pin(port).await.unwrap_or(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
     extern crate std;
     use core::marker::PhantomData;
 
-    use super::*;
-    use embedded_hal::i2c::ErrorKind;
-    use pretty_assertions::assert_eq;
-    extern crate embedded_hal_mock;
-    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
-    use tests::std::vec::Vec;
+    use super::*;
+    use embedded_hal::i2c::ErrorKind;
+    use pretty_assertions::assert_eq;
+    extern crate embedded_hal_mock;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use tests::std::vec::Vec;
+
+    fn vector1(a: u8) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.push(a);
+        v
+    }
+    fn vector2(a: u8, b: u8) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.push(a);
+        v.push(b);
+        v
+    }
+    fn vector3(a: u8, b: u8, c: u8) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.push(a);
+        v.push(b);
+        v.push(c);
+        v
+    }
+
+    #[test]
+    fn test_read_config_error() {
+        let expectations =
+            [
+                I2cTransaction::write_read(
+                    0x40,
+                    vector1(Register::Gpio as u8),
+                    vector2(0xff, 0xff),
+                )
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+        let result = mcp.read_config(Register::Gpio);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_config_success() {
+        let expectations = [I2cTransaction::write_read(
+            0x40,
+            vector1(Register::Gpio as u8),
+            vector2(0xad, 0xde),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+        let result = mcp.read_config(Register::Gpio);
+        assert_eq!(0xdead, result.unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_config_stop_then_read() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector1(Register::Gpio as u8)),
+            I2cTransaction::read(0x40, vector2(0xad, 0xde)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+        mcp.set_read_style(ReadStyle::StopThenRead);
+        let result = mcp.read_config(Register::Gpio);
+        assert_eq!(0xdead, result.unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_config_stop_then_read_error() {
+        let expectations = [I2cTransaction::write(0x40, vector1(Register::Gpio as u8))
+            .with_error(embedded_hal::i2c::ErrorKind::Other)];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+        mcp.set_read_style(ReadStyle::StopThenRead);
+        let result = mcp.read_config(Register::Gpio);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_config_error() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0x10))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+        let result = mcp.write_config(Register::Gpio, 0x10ff);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_config_success() {
+        let expectations = [I2cTransaction::write(
+            0x40,
+            vector3(Register::Gpio as u8, 0xff, 0x10),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+        let result = mcp.write_config(Register::Gpio, 0x10ff); //0xaabb
+        assert_eq!((), result.unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_new_wait_ready_success() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mcp = MCP23017::new_wait_ready(
+            i2c.clone(),
+            0x40,
+            &mut embedded_hal_mock::eh1::delay::NoopDelay,
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(0x40, mcp.address);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_new_wait_ready_timeout() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let result = MCP23017::new_wait_ready(
+            i2c.clone(),
+            0x40,
+            &mut embedded_hal_mock::eh1::delay::NoopDelay,
+            2,
+        );
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_lint_config_success() {
+        let expectations = [
+            //lint_config (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), vector2(0xff, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpinten as u8), vector2(0x01, 0x02)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Defval as u8), vector2(0x80, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gppu as u8), vector2(0xff, 0x04)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let result: Vec<ConfigDiagnostic> = mcp.lint_config().unwrap().collect();
+
+        assert_eq!(
+            std::vec![
+                ConfigDiagnostic::InterruptOnOutput(MyPort::Portb, PinNumber::Pin1),
+                ConfigDiagnostic::PullUpOnOutput(MyPort::Portb, PinNumber::Pin2),
+                ConfigDiagnostic::DefvalIgnored(MyPort::Porta, PinNumber::Pin7),
+            ],
+            result
+        );
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_lint_config_error() {
+        let expectations = [
+            //lint_config (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), vector2(0xff, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let result = mcp.lint_config();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_as_input_error() {
+        let expectations =
+            [
+                I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff))
+                    .with_error(embedded_hal::i2c::ErrorKind::Other),
+            ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_input();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), mcp.unwrap_err());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_as_input_success() {
+        let expectations = [I2cTransaction::write(
+            0x40,
+            vector3(Register::Iodir as u8, 0xff, 0xff),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_input().unwrap();
+
+        assert_eq!(0x40, mcp.address);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_as_output_error() {
+        let expectations =
+            [
+                I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00))
+                    .with_error(embedded_hal::i2c::ErrorKind::Other),
+            ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_output();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), mcp.unwrap_err());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_as_output_success() {
+        let expectations = [I2cTransaction::write(
+            0x40,
+            vector3(Register::Iodir as u8, 0x00, 0x00),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_output().unwrap();
+
+        assert_eq!(0x40, mcp.address);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_success() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x11, 0x22)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_output().unwrap();
+        assert_eq!((), mcp.write(0x2211).unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_error() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x11, 0x22))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_output().unwrap();
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            mcp.write(0x2211).unwrap_err()
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_pin_error() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0xfe))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_output().unwrap();
+
+        let result = mcp.write_pin(MyPort::Portb, PinNumber::Pin0, PinSet::Low);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_pin_success() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0xfe)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xfe, 0xff)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut mcp = mcp.set_as_output().unwrap();
+
+        let result = mcp.write_pin(MyPort::Portb, PinNumber::Pin0, PinSet::Low);
+        assert_eq!((), result.unwrap());
+        let result = mcp.write_pin(MyPort::Porta, PinNumber::Pin0, PinSet::Low);
+        assert_eq!((), result.unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_output_inversion() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xf0, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xf0, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xf1, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        mcp.set_output_inversion_mask(MyPort::Porta, 0x0f);
+        assert_eq!(0x0f, mcp.output_inversion_mask(MyPort::Porta));
+        assert_eq!(0x00, mcp.output_inversion_mask(MyPort::Portb));
+
+        mcp.write(0x00ff).unwrap();
+        mcp.write_pin(MyPort::Porta, PinNumber::Pin0, PinSet::Low)
+            .unwrap();
+
+        let plan = mcp.prepare_waveform::<1>(&[0x0000]).unwrap();
+        assert_eq!([Register::Gpio as u8, 0x0f, 0x00], plan.frames[0]);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_prepare_waveform_error() {
+        let mut i2c = I2cMock::new(&[]);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        let result = mcp.prepare_waveform::<2>(&[0x0001, 0x0002, 0x0003]);
+
+        assert_eq!(
+            Error::Protocol(ProtocolError::InvalidParameter),
+            result.unwrap_err()
+        );
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_play_success() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x01, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x00, 0x80)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        let plan = mcp.prepare_waveform::<4>(&[0x0001, 0x8000]).unwrap();
+        assert_eq!(2, plan.len());
+
+        let result = mcp.play(&plan, &mut embedded_hal_mock::eh1::delay::NoopDelay, 5);
+
+        assert_eq!((), result.unwrap());
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_play_error() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x01, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        let plan = mcp.prepare_waveform::<4>(&[0x0001, 0x8000]).unwrap();
+        let result = mcp.play(&plan, &mut embedded_hal_mock::eh1::delay::NoopDelay, 5);
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_verify_pin_success() {
+        let expectations = [
+            //verify_pin (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x08)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+        let result = mcp.verify_pin(MyPort::Portb, PinNumber::Pin3).unwrap();
+
+        assert_eq!(PinVerify::ShortToGround, result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_verify_pin_error() {
+        let expectations = [
+            //verify_pin (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x08))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+        let result = mcp.verify_pin(MyPort::Portb, PinNumber::Pin3).unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_run_sequence_success() {
+        let expectations = [
+            //run_sequence (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            //step 0 (write_config and read back)
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x0f, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x0f, 0x00)),
+            //step 1 (write_config and read back, pin B0 outside the mask reads differently)
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x0f, 0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x0f, 0x81)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+        let steps = [
+            SequenceStep {
+                mask: 0x00ff,
+                value: 0x000f,
+                min_dwell_us: 10,
+            },
+            SequenceStep {
+                mask: 0x8000,
+                value: 0xffff,
+                min_dwell_us: 10,
+            },
+        ];
+
+        let result = mcp
+            .run_sequence(&steps, &mut embedded_hal_mock::eh1::delay::NoopDelay)
+            .unwrap();
+
+        let crc = [0x0f, 0x00, 0x0f, 0x80]
+            .iter()
+            .fold(0xFFFF, |crc, byte| crc16_update(crc, *byte));
+        assert_eq!(
+            SequenceReport {
+                steps_applied: 2,
+                crc,
+                outcome: SequenceOutcome::Completed,
+            },
+            result
+        );
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_run_sequence_verify_failed() {
+        let expectations = [
+            //run_sequence (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            //step 0 (write_config and read back)
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x01, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+        let steps = [
+            SequenceStep {
+                mask: 0x0001,
+                value: 0x0001,
+                min_dwell_us: 10,
+            },
+            SequenceStep {
+                mask: 0x0002,
+                value: 0x0002,
+                min_dwell_us: 10,
+            },
+        ];
+
+        let result = mcp
+            .run_sequence(&steps, &mut embedded_hal_mock::eh1::delay::NoopDelay)
+            .unwrap();
+
+        assert_eq!(
+            SequenceReport {
+                steps_applied: 0,
+                crc: 0xFFFF,
+                outcome: SequenceOutcome::VerifyFailed {
+                    step: 0,
+                    expected: 0x0001,
+                    read_back: 0x0000,
+                },
+            },
+            result
+        );
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_safe_on_drop() {
+        let expectations = [
+            //write
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0xff)),
+            //drop (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x00, 0x01)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x00, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        let mut safe = mcp.safe_on_drop(0x0100);
+        safe.write(0xffff).unwrap();
+        drop(safe);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_safe_on_drop_error() {
+        let expectations = [
+            //drop (write_config), errors are ignored
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x00, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        drop(mcp.safe_on_drop(0x0000));
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_pull_success() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Gppu as u8, 0x00, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut result = mcp.set_as_input().unwrap().set_pull(PinSet::Low).unwrap();
+
+        assert_eq!(0x40, result.address);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_pull_error() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Gppu as u8, 0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut result = mcp
+            .set_as_input()
+            .unwrap()
+            .set_pull(PinSet::Low)
+            .unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(not(feature = "strict"))] // IOCON bit 0 is unimplemented and never reads back as 1
+    fn test_set_interrupt_mirror_error() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_interrupt_mirror (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0xff, 0xff)),
+            //set_interrupt_mirror (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0xbf, 0xbf))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut result = mcp
+            .set_as_input()
+            .unwrap()
+            .set_interrupt_mirror(InterruptMirror::MirrorOff)
+            .unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(not(feature = "strict"))] // IOCON bit 0 is unimplemented and never reads back as 1
+    fn test_set_interrupt_mirror_success() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_interrupt_mirror (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0xff, 0xff)),
+            //set_interrupt_mirror (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0xbf, 0xbf)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut result = mcp
+            .set_as_input()
+            .unwrap()
+            .set_interrupt_mirror(InterruptMirror::MirrorOff)
+            .unwrap();
+
+        assert_eq!(0x40, result.address);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_interrupt_on_error() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_interrupt_on (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xdd)),
+            //set_interrupt_on (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Intcon as u8, 0xff, 0xdc))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut result = mcp
+            .set_as_input()
+            .unwrap()
+            .set_interrupt_on(MyPort::Portb, PinNumber::Pin0, InterruptOn::PinChange)
+            .unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_interrupt_on_success() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_interrupt_on (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xdd)),
+            //set_interrupt_on (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Intcon as u8, 0xff, 0xdc)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let mut result = mcp
+            .set_as_input()
+            .unwrap()
+            .set_interrupt_on(MyPort::Portb, PinNumber::Pin0, InterruptOn::PinChange)
+            .unwrap();
+
+        assert_eq!(0x40, result.address);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_interrupt_compare_error() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_interrupt_compare (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xff)),
+            //set_interrupt_compare (write_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Defval as u8), vector2(0xff, 0xff)),
+            I2cTransaction::write(0x40, vector3(Register::Defval as u8, 0xfe, 0xff))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
 
-    fn vector1(a: u8) -> Vec<u8> {
-        let mut v = Vec::new();
-        v.push(a);
-        v
-    }
-    fn vector2(a: u8, b: u8) -> Vec<u8> {
-        let mut v = Vec::new();
-        v.push(a);
-        v.push(b);
-        v
-    }
-    fn vector3(a: u8, b: u8, c: u8) -> Vec<u8> {
-        let mut v = Vec::new();
-        v.push(a);
-        v.push(b);
-        v.push(c);
-        v
+        let mut result = mcp
+            .set_as_input()
+            .unwrap()
+            .set_interrupt_compare(MyPort::Porta, PinNumber::Pin0, PinSet::Low)
+            .unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+
+        //finalize execution
+        i2c.done();
     }
 
     #[test]
-    fn test_read_config_error() {
-        let expectations =
-            [
-                I2cTransaction::write_read(
-                    0x40,
-                    vector1(Register::Gpio as u8),
-                    vector2(0xff, 0xff),
-                )
-                .with_error(embedded_hal::i2c::ErrorKind::Other),
-            ];
+    fn test_set_interrupt_compare_success() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+            //set_interrupt_compare (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xff)),
+            //set_interrupt_compare (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Defval as u8), vector2(0xff, 0xff)),
+            //set_interrupt_compare (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Defval as u8, 0xfe, 0xff)),
+        ];
+
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
-        let result = mcp.read_config(Register::Gpio);
-        assert_eq!(Error::CommunicationErr, result.unwrap_err());
+
+        let mut result = mcp
+            .set_as_input()
+            .unwrap()
+            .set_interrupt_compare(MyPort::Porta, PinNumber::Pin0, PinSet::Low)
+            .unwrap();
+
+        assert_eq!(0x40, result.address);
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_read_config_success() {
-        let expectations = [I2cTransaction::write_read(
-            0x40,
-            vector1(Register::Gpio as u8),
-            vector2(0xad, 0xde),
-        )];
+    fn test_ready_success() {
+        let expectations = [
+            //set_as_input (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
+        ];
+
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
-        let result = mcp.read_config(Register::Gpio);
-        assert_eq!(0xdead, result.unwrap());
+
+        let mut result = mcp.set_as_input().unwrap().ready();
+
+        let compare = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        assert_eq!(compare.address, result.address);
+        assert_eq!(compare.state, result.state);
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_write_config_error() {
+    fn test_read_error() {
         let expectations = [
-            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0x10))
+            //read
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp.read().unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_success() {
+        let expectations = [
+            //read
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xad, 0xde)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp.read().unwrap();
+
+        assert_eq!(0xdead, result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_pin_error() {
+        let expectations = [
+            //read_pin
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xad, 0xde))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp.read_pin(MyPort::Porta, PinNumber::Pin0).unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_pin_success() {
+        let expectations = [
+            //read_pin
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8),
+                vector2(0x00, 0b00000001),
+            ),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp.read_pin(MyPort::Portb, PinNumber::Pin0).unwrap();
+
+        assert_eq!(1, result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_disable_interrupt_error() {
+        let expectations = [
+            //disable interrupt (read_config)
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpinten as u8),
+                vector2(0x00, 0b00000001),
+            ),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0, 0))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp
+            .disable_interrupt(MyPort::Portb, PinNumber::Pin0)
+            .unwrap_err();
+
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_disable_interrupt_success() {
+        let expectations = [
+            //disable interrupt (read_config)
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpinten as u8),
+                vector2(0x00, 0b00000001),
+            ),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0, 0)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp
+            .disable_interrupt(MyPort::Portb, PinNumber::Pin0)
+            .unwrap();
+
+        assert_eq!((), result);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_arm_disarm() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x01, 0x80)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x01, 0x80))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            //enable_interrupt and disable_interrupt update the staged interrupts
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x01, 0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpinten as u8), vector2(0x01, 0x80)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x03, 0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpinten as u8), vector2(0x03, 0x80)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x03, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x03, 0x00)),
+            //failed write leaves the staged interrupts unchanged
+            I2cTransaction::write_read(0x40, vector1(Register::Gpinten as u8), vector2(0x03, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0x03, 0x01))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputConfiguring>,
+        };
+        let mut mcp = mcp
+            .stage_interrupt(MyPort::Porta, PinNumber::Pin0)
+            .stage_interrupt(MyPort::Portb, PinNumber::Pin7)
+            .ready();
+        assert_eq!(0x8001, mcp.staged_interrupts());
+
+        mcp.arm().unwrap();
+        mcp.disarm().unwrap();
+        assert_eq!(Error::Transport(ErrorKind::Other), mcp.arm().unwrap_err());
+
+        mcp.arm().unwrap();
+        mcp.enable_interrupt(MyPort::Porta, PinNumber::Pin1)
+            .unwrap();
+        mcp.disable_interrupt(MyPort::Portb, PinNumber::Pin7)
+            .unwrap();
+        assert_eq!(0x0003, mcp.staged_interrupts());
+        mcp.disarm().unwrap();
+        mcp.arm().unwrap();
+
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            mcp.enable_interrupt(MyPort::Portb, PinNumber::Pin0)
+                .unwrap_err()
+        );
+        assert_eq!(0x0003, mcp.staged_interrupts());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_enable_interrupt_error() {
+        let expectations = [
+            //enable_interrupt (read_config)
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpinten as u8),
+                vector2(0b00000000, 0b00000000),
+            ),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 1, 0))
                 .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
+
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
-        let result = mcp.write_config(Register::Gpio, 0x10ff);
-        assert_eq!(Error::CommunicationErr, result.unwrap_err());
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp
+            .enable_interrupt(MyPort::Porta, PinNumber::Pin0)
+            .unwrap_err();
 
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_write_config_success() {
-        let expectations = [I2cTransaction::write(
-            0x40,
-            vector3(Register::Gpio as u8, 0xff, 0x10),
-        )];
+    fn test_enable_interrupt_success() {
+        let expectations = [
+            //enable_interrupt (read_config)
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpinten as u8),
+                vector2(0b00000000, 0b00000000),
+            ),
+            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 1, 0)),
+        ];
+
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
-        let result = mcp.write_config(Register::Gpio, 0x10ff); //0xaabb
-        assert_eq!((), result.unwrap());
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp
+            .enable_interrupt(MyPort::Porta, PinNumber::Pin0)
+            .unwrap();
 
+        assert_eq!((), result);
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_as_input_error() {
-        let expectations =
-            [
-                I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff))
-                    .with_error(embedded_hal::i2c::ErrorKind::Other),
-            ];
-        let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
-
-        let mut mcp = mcp.set_as_input();
+    fn test_diagnose_spurious_interrupt_none() {
+        let expectations = [
+            //diagnose_spurious_interrupt (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x01, 0x00)),
+        ];
 
-        assert_eq!(Error::CommunicationErr, mcp.unwrap_err());
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp.diagnose_spurious_interrupt(MyPort::Porta).unwrap();
 
+        assert_eq!(None, result);
+        assert_eq!(0, mcp.spurious_interrupt_count());
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_as_input_success() {
-        let expectations = [I2cTransaction::write(
-            0x40,
-            vector3(Register::Iodir as u8, 0xff, 0xff),
-        )];
+    fn test_diagnose_spurious_interrupt_success() {
+        let expectations = [
+            //mirrored interrupt from the other port
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x04)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x40, 0x40)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x04)),
+            //capture differs from the current level
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x01, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            //nothing captured
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x01, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x01, 0x00)),
+        ];
+
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
 
-        let mut mcp = mcp.set_as_input().unwrap();
+        let result = mcp.diagnose_spurious_interrupt(MyPort::Porta).unwrap();
+        assert_eq!(Some(SpuriousInterrupt::MirrorMisconfig), result);
+        let result = mcp.diagnose_spurious_interrupt(MyPort::Porta).unwrap();
+        assert_eq!(Some(SpuriousInterrupt::ClearedByRaceRead), result);
+        let result = mcp.diagnose_spurious_interrupt(MyPort::Porta).unwrap();
+        assert_eq!(Some(SpuriousInterrupt::NoiseOnIntLine), result);
 
-        assert_eq!(0x40, mcp.address);
+        assert_eq!(3, mcp.spurious_interrupt_count());
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_as_output_error() {
-        let expectations =
-            [
-                I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00))
-                    .with_error(embedded_hal::i2c::ErrorKind::Other),
-            ];
-        let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
-
-        let mut mcp = mcp.set_as_output();
+    fn test_get_interrupted_pin_error() {
+        let expectations = [
+            //get_interrupted_pin (read_config)
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intf as u8),
+                vector2(0x00, 0b11111111),
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
 
-        assert_eq!(Error::CommunicationErr, mcp.unwrap_err());
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp.get_interrupted_pin(MyPort::Porta);
 
+        assert_eq!(None, result);
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_as_output_success() {
-        let expectations = [I2cTransaction::write(
-            0x40,
-            vector3(Register::Iodir as u8, 0x00, 0x00),
-        )];
-        let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
+    fn test_get_interrupted_pin_success() {
+        let expectations = [
+            //get_interrupted_pin (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x80)),
+        ];
 
-        let mut mcp = mcp.set_as_output().unwrap();
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        let result = mcp.get_interrupted_pin(MyPort::Portb);
 
-        assert_eq!(0x40, mcp.address);
+        assert_eq!(Some(PinNumber::Pin7), result);
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_write_success() {
+    fn test_read_pin_level() {
         let expectations = [
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
-            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x11, 0x22)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x01)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x01)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
 
-        let mut mcp = mcp.set_as_output().unwrap();
-        assert_eq!((), mcp.write(0x2211).unwrap());
+        let result = mcp.read_pin_level(MyPort::Portb, PinNumber::Pin0).unwrap();
+        assert_eq!(PinSet::High, result);
+        let result = mcp.read_pin_level(MyPort::Porta, PinNumber::Pin0).unwrap();
+        assert_eq!(PinSet::Low, result);
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_write_error() {
+    fn test_post_write_verify() {
         let expectations = [
             I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
-            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x11, 0x22))
-                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x12, 0x34)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x12, 0x30)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x12, 0x34)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x12, 0x34)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x00)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
-
+        mcp.set_post_write_verify(PostWriteVerify::Retries(1));
         let mut mcp = mcp.set_as_output().unwrap();
-        assert_eq!(Error::CommunicationErr, mcp.write(0x2211).unwrap_err());
+
+        mcp.write(0x3412).unwrap();
+        assert_eq!(1, mcp.write_corrections());
+        assert_eq!(
+            Error::Protocol(ProtocolError::WriteVerifyFailed),
+            mcp.write(0x00ff).unwrap_err()
+        );
+        assert_eq!(2, mcp.write_corrections());
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_write_pin_error() {
+    fn test_format_port() {
         let expectations = [
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
-            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff)),
-            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0xfe))
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0xa5)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0xa5))
                 .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
+        let mut text = std::string::String::new();
 
-        let mut mcp = mcp.set_as_output().unwrap();
-
-        let result = mcp.write_pin(MyPort::Portb, PinNumber::Pin0, PinSet::Low);
-        assert_eq!(Error::CommunicationErr, result.unwrap_err());
+        mcp.format_port(MyPort::Portb, &mut text, FormatStyle::Hex)
+            .unwrap();
+        assert_eq!("0xa5", text);
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            mcp.format_port(MyPort::Portb, &mut text, FormatStyle::Binary)
+                .unwrap_err()
+        );
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_write_pin_success() {
-        let expectations = [
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
-            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff)),
-            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xff, 0xfe)),
-            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff)),
-            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0xfe, 0xff)),
-        ];
-
-        let mut i2c = I2cMock::new(&expectations);
+    #[cfg(feature = "strict")]
+    fn test_strict_reserved_bits() {
+        let mut i2c = I2cMock::new(&[]);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
 
-        let mut mcp = mcp.set_as_output().unwrap();
-
-        let result = mcp.write_pin(MyPort::Portb, PinNumber::Pin0, PinSet::Low);
-        assert_eq!((), result.unwrap());
-        let result = mcp.write_pin(MyPort::Porta, PinNumber::Pin0, PinSet::Low);
-        assert_eq!((), result.unwrap());
+        let result = mcp.write_config(Register::Iocon, 0x0100);
+        assert_eq!(
+            Error::Protocol(ProtocolError::ReservedBits),
+            result.unwrap_err()
+        );
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_pull_success() {
+    fn test_register_pairs() {
         let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Gppu as u8, 0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), vector2(0xff, 0x0f)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x01, 0x80)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
-
-        let mut result = mcp.set_as_input().unwrap().set_pull(PinSet::Low).unwrap();
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
 
-        assert_eq!(0x40, result.address);
+        let iodir = mcp.read_iodir().unwrap();
+        assert_eq!(0x0f, iodir.portb());
+        let gpio = mcp.read_gpio().unwrap();
+        assert_eq!(PinSet::High, gpio.pin(MyPort::Porta, PinNumber::Pin0));
+        assert_eq!(PinSet::High, gpio.pin(MyPort::Portb, PinNumber::Pin7));
+        assert_eq!(PinSet::Low, gpio.pin(MyPort::Portb, PinNumber::Pin0));
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_pull_error() {
+    fn test_gpio_name() {
         let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Gppu as u8, 0x00, 0x00))
-                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x00, 0x08)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x80, 0x00)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
-
-        let mut result = mcp
-            .set_as_input()
-            .unwrap()
-            .set_pull(PinSet::Low)
-            .unwrap_err();
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+        mcp.write_gpio(GpioName::GPB3, PinSet::High).unwrap();
 
-        assert_eq!(Error::CommunicationErr, result);
+        let mut mcp = MCP23017 {
+            i2c: mcp.i2c,
+            address: mcp.address,
+            read_style: mcp.read_style,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+        assert_eq!(PinSet::High, mcp.read_gpio_level(GpioName::GPA7).unwrap());
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_interrupt_mirror_error() {
+    fn test_set_polarity() {
         let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_interrupt_mirror (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0xff, 0xff)),
-            //set_interrupt_mirror (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0xbf, 0xbf))
-                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Ipol as u8), vector2(0x00, 0x01)),
+            I2cTransaction::write(0x40, vector3(Register::Ipol as u8, 0x04, 0x01)),
+            I2cTransaction::write_read(0x40, vector1(Register::Ipol as u8), vector2(0x04, 0x01)),
+            I2cTransaction::write(0x40, vector3(Register::Ipol as u8, 0x04, 0x00)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
+        let mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputConfiguring>,
+        };
 
-        let mut result = mcp
-            .set_as_input()
+        mcp.set_polarity(MyPort::Porta, PinNumber::Pin2, Polarity::Inverted)
             .unwrap()
-            .set_interrupt_mirror(InterruptMirror::MirrorOff)
-            .unwrap_err();
-
-        assert_eq!(Error::CommunicationErr, result);
+            .set_polarity(MyPort::Portb, PinNumber::Pin0, Polarity::Normal)
+            .unwrap();
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_interrupt_mirror_success() {
+    fn test_get_interrupt_capture() {
         let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_interrupt_mirror (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0xff, 0xff)),
-            //set_interrupt_mirror (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0xbf, 0xbf)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x01, 0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x10)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x00, 0x10)),
+            //two pins flagged
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x24)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x00, 0x20)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
-
-        let mut result = mcp
-            .set_as_input()
-            .unwrap()
-            .set_interrupt_mirror(InterruptMirror::MirrorOff)
-            .unwrap();
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<InputReady>,
+        };
 
-        assert_eq!(0x40, result.address);
+        assert_eq!(0x80, mcp.get_interrupt_capture(MyPort::Portb).unwrap());
+        assert_eq!(None, mcp.get_interrupted_pin_level(MyPort::Portb).unwrap());
+        assert_eq!(
+            Some((PinNumber::Pin4, PinSet::High)),
+            mcp.get_interrupted_pin_level(MyPort::Portb).unwrap()
+        );
+        assert_eq!(
+            Some((PinNumber::Pin2, PinSet::Low)),
+            mcp.get_interrupted_pin_level(MyPort::Portb).unwrap()
+        );
+        assert_eq!(
+            Error::Transport(embedded_hal::i2c::ErrorKind::Other),
+            mcp.read_interrupt_capture().unwrap_err()
+        );
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_interrupt_on_error() {
+    fn test_set_interrupt_output() {
         let expectations = [
             //set_as_input (write_config)
             I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_interrupt_on (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xdd)),
-            //set_interrupt_on (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Intcon as u8, 0xff, 0xdc))
+            //set_interrupt_output (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x42, 0x42)),
+            //set_interrupt_output (write_config)
+            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0x44, 0x44)),
+            //set_interrupt_output (read_config)
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x44, 0x44))
                 .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+        let mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
 
-        let mut result = mcp
+        let result = mcp
             .set_as_input()
             .unwrap()
-            .set_interrupt_on(MyPort::Portb, PinNumber::Pin0, InterruptOn::PinChange)
+            .set_interrupt_output(InterruptOutput::OpenDrain)
+            .unwrap()
+            .set_interrupt_output(InterruptOutput::ActiveHigh)
             .unwrap_err();
 
-        assert_eq!(Error::CommunicationErr, result);
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_interrupt_on_success() {
+    fn test_apply_transaction_committed() {
         let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_interrupt_on (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xdd)),
-            //set_interrupt_on (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Intcon as u8, 0xff, 0xdc)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0xf0)),
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8, 0x01)),
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8 | 0x01, 0x0f)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
-            MCP23017::new(i2c.clone(), 0x40);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
 
-        let mut result = mcp
-            .set_as_input()
-            .unwrap()
-            .set_interrupt_on(MyPort::Portb, PinNumber::Pin0, InterruptOn::PinChange)
-            .unwrap();
+        let transaction = OutputTransaction::new()
+            .with_pin(MyPort::Porta, PinNumber::Pin0, PinSet::High)
+            .with_port(MyPort::Portb, 0x0f);
+        let report = mcp.apply_transaction(&transaction).unwrap();
+
+        assert_eq!(
+            TransactionReport {
+                outcome: TransactionOutcome::Committed,
+                output: 0x0f01,
+            },
+            report
+        );
+        //finalize execution
+        i2c.done();
+    }
 
-        assert_eq!(0x40, result.address);
+    #[test]
+    fn test_apply_transaction_rollback() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8, 0x80)),
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8 | 0x01, 0x01))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            //rollback
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8, 0x00)),
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8 | 0x01, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8 | 0x01, 0x02))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            //rollback
+            I2cTransaction::write(0x40, vector2(Register::Olat as u8 | 0x01, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x00))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        let transaction = OutputTransaction::new()
+            .with_pin(MyPort::Porta, PinNumber::Pin7, PinSet::High)
+            .with_pin(MyPort::Portb, PinNumber::Pin0, PinSet::High);
+        let report = mcp.apply_transaction(&transaction).unwrap();
+        assert_eq!(TransactionOutcome::RollbackFailed, report.outcome);
+        assert_eq!(0x0000, report.output);
+
+        let transaction = OutputTransaction::new().with_port(MyPort::Portb, 0x02);
+        let report = mcp.apply_transaction(&transaction).unwrap();
+        assert_eq!(
+            TransactionReport {
+                outcome: TransactionOutcome::RolledBack,
+                output: 0x0000,
+            },
+            report
+        );
+
+        let result = mcp.apply_transaction(&transaction).unwrap_err();
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_interrupt_compare_error() {
+    fn test_set_address_mode() {
         let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_interrupt_compare (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xff)),
-            //set_interrupt_compare (write_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Defval as u8), vector2(0xff, 0xff)),
-            I2cTransaction::write(0x40, vector3(Register::Defval as u8, 0xfe, 0xff))
-                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x40, 0x40)),
+            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0x60, 0x60)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x60, 0x60)),
+            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0x40, 0x40)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
 
-        let mut result = mcp
-            .set_as_input()
-            .unwrap()
-            .set_interrupt_compare(MyPort::Porta, PinNumber::Pin0, PinSet::Low)
-            .unwrap_err();
-
-        assert_eq!(Error::CommunicationErr, result);
+        mcp.set_address_mode(AddressMode::Byte).unwrap();
+        mcp.set_address_mode(AddressMode::Sequential).unwrap();
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_set_interrupt_compare_success() {
+    fn test_read_all_registers() {
+        let registers: Vec<u8> = (0..REGISTER_FILE_LEN as u8).collect();
         let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-            //set_interrupt_compare (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Intcon as u8), vector2(0xff, 0xff)),
-            //set_interrupt_compare (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Defval as u8), vector2(0xff, 0xff)),
-            //set_interrupt_compare (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Defval as u8, 0xfe, 0xff)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), registers.clone()),
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), registers.clone())
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
 
-        let mut result = mcp
-            .set_as_input()
-            .unwrap()
-            .set_interrupt_compare(MyPort::Porta, PinNumber::Pin0, PinSet::Low)
-            .unwrap();
-
-        assert_eq!(0x40, result.address);
+        assert_eq!(registers.as_slice(), mcp.read_all_registers().unwrap());
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            mcp.read_all_registers().unwrap_err()
+        );
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_ready_success() {
-        let expectations = [
-            //set_as_input (write_config)
-            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0xff, 0xff)),
-        ];
+    fn test_write_multiple() {
+        let expectations = [I2cTransaction::write(
+            0x40,
+            vector3(Register::Gppu as u8, 0xff, 0x0f),
+        )];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
             MCP23017::new(i2c.clone(), 0x40);
 
-        let mut result = mcp.set_as_input().unwrap().ready();
+        mcp.write_multiple(Register::Gppu, &[0xff, 0x0f]).unwrap();
+        assert_eq!(
+            Error::Protocol(ProtocolError::InvalidParameter),
+            mcp.write_multiple(Register::Olat, &[0x00, 0x00, 0x00])
+                .unwrap_err()
+        );
+        assert_eq!(
+            Error::Protocol(ProtocolError::InvalidParameter),
+            mcp.write_multiple(Register::Iodir, &[]).unwrap_err()
+        );
 
-        let compare = MCP23017 {
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_sample_duty() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x02)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x02))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
             state: core::marker::PhantomData::<InputReady>,
         };
-        assert_eq!(compare.address, result.address);
-        assert_eq!(compare.state, result.state);
+        let mut sampler = DutySampler::new(4, 100);
+
+        let result = mcp.sample_duty(
+            MyPort::Portb,
+            PinNumber::Pin1,
+            &mut sampler,
+            500,
+            &mut embedded_hal_mock::eh1::delay::NoopDelay,
+        );
+        assert_eq!(25, result.unwrap());
+
+        let result = mcp.sample_duty(
+            MyPort::Portb,
+            PinNumber::Pin1,
+            &mut sampler,
+            500,
+            &mut embedded_hal_mock::eh1::delay::NoopDelay,
+        );
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
 
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_read_error() {
+    fn test_interrupt_manager() {
+        use core::sync::atomic::{AtomicU16, Ordering};
+        static HANDLED: AtomicU16 = AtomicU16::new(0);
+
+        fn handler(event: InterruptEvent) {
+            let name = GpioName::from((event.port, event.pin));
+            HANDLED.fetch_or(1 << name as u16, Ordering::Relaxed);
+        }
+
         let expectations = [
-            //read
-            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xff, 0xff))
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x01, 0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x01, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x01, 0x00))
                 .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp = MCP23017 {
+        let mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
             state: core::marker::PhantomData::<InputReady>,
         };
-        let result = mcp.read().unwrap_err();
+        let mut manager = InterruptManager::new(mcp);
+        assert!(manager.register(GpioName::GPB7, handler).is_none());
+
+        assert!(manager.on_interrupt().unwrap().is_empty());
+        assert_eq!(
+            &[
+                InterruptEvent {
+                    port: MyPort::Porta,
+                    pin: PinNumber::Pin0,
+                    level: PinSet::High,
+                },
+                InterruptEvent {
+                    port: MyPort::Portb,
+                    pin: PinNumber::Pin7,
+                    level: PinSet::Low,
+                },
+            ],
+            manager.on_interrupt().unwrap().as_slice()
+        );
+        assert_eq!(0x8000, HANDLED.load(Ordering::Relaxed));
+        assert!(manager.unregister(GpioName::GPB7).is_some());
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            manager.on_interrupt().unwrap_err()
+        );
 
-        assert_eq!(Error::CommunicationErr, result);
         //finalize execution
+        manager.release();
         i2c.done();
     }
 
     #[test]
-    fn test_read_success() {
+    fn test_get_interrupted_pins() {
         let expectations = [
-            //read
-            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xad, 0xde)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x81)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x81))
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
             state: core::marker::PhantomData::<InputReady>,
         };
-        let result = mcp.read().unwrap();
 
-        assert_eq!(0xdead, result);
+        assert_eq!(
+            PinFlags(0x81),
+            mcp.get_interrupted_pins(MyPort::Portb).unwrap()
+        );
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            mcp.get_interrupted_pins(MyPort::Porta).unwrap_err()
+        );
+
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_read_pin_error() {
+    fn test_try_get_interrupted_pin() {
         let expectations = [
-            //read_pin
-            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0xad, 0xde))
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x04, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x00))
                 .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
@@ -973,104 +3912,236 @@ mod tests {
         let mut mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
             state: core::marker::PhantomData::<InputReady>,
         };
-        let result = mcp.read_pin(MyPort::Porta, PinNumber::Pin0).unwrap_err();
 
-        assert_eq!(Error::CommunicationErr, result);
+        assert_eq!(
+            Some(PinNumber::Pin2),
+            mcp.try_get_interrupted_pin(MyPort::Porta).unwrap()
+        );
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            mcp.try_get_interrupted_pin(MyPort::Porta).unwrap_err()
+        );
+
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_read_pin_success() {
+    fn test_apply_config() {
         let expectations = [
-            //read_pin
-            I2cTransaction::write_read(
+            I2cTransaction::write(
                 0x40,
-                vector1(Register::Gpio as u8),
-                vector2(0x00, 0b00000001),
+                std::vec![
+                    Register::Iodir as u8,
+                    0xff,
+                    0x00,
+                    0x01,
+                    0x00,
+                    0x0f,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x40,
+                    0x40,
+                    0xff,
+                    0x00,
+                ],
+            ),
+            I2cTransaction::write(
+                0x40,
+                std::vec![
+                    Register::Iodir as u8,
+                    0xff,
+                    0xff,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                ],
             ),
+            I2cTransaction::write(0x40, vector3(Register::Iocon as u8, 0x20, 0x20)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp = MCP23017 {
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let config = ConfigBuilder::new()
+            .with_iodir(0x00ff)
+            .with_ipol(0x0001)
+            .with_gpinten(0x000f)
+            .with_iocon(0x40)
+            .with_gppu(GppuPair::default().with_port(MyPort::Porta, 0xff));
+        mcp.apply_config(&config).unwrap();
+        mcp.apply_config(&ConfigBuilder::new().with_iocon(0x20))
+            .unwrap();
+        assert_eq!(
+            Error::Protocol(ProtocolError::InvalidParameter),
+            mcp.apply_config(&ConfigBuilder::new().with_iocon(0x80))
+                .unwrap_err()
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_interrupt_manager_stall() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x01, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
             state: core::marker::PhantomData::<InputReady>,
         };
-        let result = mcp.read_pin(MyPort::Portb, PinNumber::Pin0).unwrap();
+        let mut manager = InterruptManager::new(mcp);
+
+        assert_eq!(None, manager.on_tick(true).unwrap());
+        manager.set_stall_threshold(2);
+        assert_eq!(None, manager.on_tick(true).unwrap());
+        manager.on_interrupt().unwrap();
+        assert_eq!(None, manager.on_tick(true).unwrap());
+        assert_eq!(None, manager.on_tick(false).unwrap());
+        assert_eq!(None, manager.on_tick(true).unwrap());
+        assert_eq!(
+            Some(ServiceStall {
+                asserted_ticks: 2,
+                intcap: 0x0001,
+                gpio: 0x0000,
+            }),
+            manager.on_tick(true).unwrap()
+        );
 
-        assert_eq!(1, result);
         //finalize execution
+        manager.release();
         i2c.done();
     }
 
     #[test]
-    fn test_disable_interrupt_error() {
+    fn test_dump_restore_registers() {
+        let mut registers = [0u8; REGISTER_FILE_LEN];
+        registers[Register::Iodir as usize] = 0x0f;
+        registers[Register::Iodir as usize + 1] = 0xf0;
+        registers[Register::Iocon as usize] = 0x40;
+        registers[Register::Iocon as usize + 1] = 0x40;
+        registers[Register::Intcap as usize] = 0x01;
+        registers[Register::Olat as usize + 1] = 0x08;
         let expectations = [
-            //disable interrupt (read_config)
-            I2cTransaction::write_read(
+            I2cTransaction::write_read(0x40, vector1(Register::Iodir as u8), registers.to_vec()),
+            I2cTransaction::write(0x40, vector3(Register::Olat as u8, 0x00, 0x08)),
+            I2cTransaction::write(
                 0x40,
-                vector1(Register::Gpinten as u8),
-                vector2(0x00, 0b00000001),
+                std::vec![
+                    Register::Iodir as u8,
+                    0x0f,
+                    0xf0,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x40,
+                    0x40,
+                    0x00,
+                    0x00,
+                ],
             ),
-            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0, 0))
-                .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp = MCP23017 {
-            i2c: i2c.clone(),
-            address: 0x40,
-            state: core::marker::PhantomData::<InputReady>,
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        let snapshot = mcp.dump_registers().unwrap();
+        assert_eq!(0xf0, snapshot.iodirb);
+        assert_eq!(0x01, snapshot.intcapa);
+        assert_eq!(registers, <[u8; REGISTER_FILE_LEN]>::from(snapshot));
+
+        mcp.restore_registers(&snapshot).unwrap();
+        let mismatch = RegisterSnapshot {
+            ioconb: 0x00,
+            ..snapshot
         };
-        let result = mcp
-            .disable_interrupt(MyPort::Portb, PinNumber::Pin0)
-            .unwrap_err();
+        assert_eq!(
+            Error::Protocol(ProtocolError::InvalidParameter),
+            mcp.restore_registers(&mismatch).unwrap_err()
+        );
 
-        assert_eq!(Error::CommunicationErr, result);
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_disable_interrupt_success() {
+    fn test_write_pin_latch() {
         let expectations = [
-            //disable interrupt (read_config)
-            I2cTransaction::write_read(
-                0x40,
-                vector1(Register::Gpinten as u8),
-                vector2(0x00, 0b00000001),
-            ),
-            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 0, 0)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x01)),
+            I2cTransaction::write(0x40, vector3(Register::Olat as u8, 0x01, 0x01)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x01, 0x01)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
-            state: core::marker::PhantomData::<InputReady>,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0x0100,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Retries(1),
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
         };
-        let result = mcp
-            .disable_interrupt(MyPort::Portb, PinNumber::Pin0)
+
+        mcp.write_pin_latch(MyPort::Porta, PinNumber::Pin0, PinSet::High)
             .unwrap();
 
-        assert_eq!((), result);
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_enable_interrupt_error() {
+    fn test_modify_toggle_pins() {
         let expectations = [
-            //enable_interrupt (read_config)
-            I2cTransaction::write_read(
-                0x40,
-                vector1(Register::Gpinten as u8),
-                vector2(0b00000000, 0b00000000),
-            ),
-            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 1, 0))
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x0f, 0x11)),
+            I2cTransaction::write(0x40, vector3(Register::Olat as u8, 0x0f, 0x81)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x0f, 0x81)),
+            I2cTransaction::write(0x40, vector3(Register::Olat as u8, 0xf0, 0x81)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0xf0, 0x81))
                 .with_error(embedded_hal::i2c::ErrorKind::Other),
         ];
 
@@ -1078,86 +4149,214 @@ mod tests {
         let mut mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
-            state: core::marker::PhantomData::<InputReady>,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
         };
-        let result = mcp
-            .enable_interrupt(MyPort::Porta, PinNumber::Pin0)
-            .unwrap_err();
 
-        assert_eq!(Error::CommunicationErr, result);
+        mcp.modify_pins(MyPort::Portb, 0x80, 0x10).unwrap();
+        mcp.toggle_pins(MyPort::Porta, 0xff).unwrap();
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            mcp.toggle_pins(MyPort::Porta, 0x01).unwrap_err()
+        );
+
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_enable_interrupt_success() {
+    fn test_output_cache() {
         let expectations = [
-            //enable_interrupt (read_config)
-            I2cTransaction::write_read(
-                0x40,
-                vector1(Register::Gpinten as u8),
-                vector2(0b00000000, 0b00000000),
-            ),
-            I2cTransaction::write(0x40, vector3(Register::Gpinten as u8, 1, 0)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x01, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x03, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x03, 0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x00, 0x01)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
-            state: core::marker::PhantomData::<InputReady>,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
         };
-        let result = mcp
-            .enable_interrupt(MyPort::Porta, PinNumber::Pin0)
+
+        assert!(!mcp.output_cache_enabled());
+        mcp.enable_output_cache().unwrap();
+        assert!(mcp.output_cache_enabled());
+        mcp.write_pin(MyPort::Porta, PinNumber::Pin1, PinSet::High)
+            .unwrap();
+        mcp.write_pin(MyPort::Portb, PinNumber::Pin7, PinSet::High)
+            .unwrap();
+
+        mcp.disable_output_cache();
+        mcp.write_pin(MyPort::Portb, PinNumber::Pin0, PinSet::High)
             .unwrap();
 
-        assert_eq!((), result);
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_get_interrupted_pin_error() {
+    fn test_recover_addressing() {
         let expectations = [
-            //get_interrupted_pin (read_config)
-            I2cTransaction::write_read(
-                0x40,
-                vector1(Register::Intf as u8),
-                vector2(0x00, 0b11111111),
-            )
-            .with_error(embedded_hal::i2c::ErrorKind::Other),
+            //bank 0
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTA), vector1(0x00)),
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTB), vector1(0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x40, 0x40)),
+            //bank 1, rewritten
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTA), vector1(0xc0)),
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTB), vector1(0xc0)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x12, 0x00)),
+            I2cTransaction::write(0x40, vector2(IOCON_BANK1_PORTA, 0x40)),
+            //ambiguous: bank 0 with IOCON = 0 and GPINTENB = OLATB = 0x80, or bank 1 with
+            //OLATA = 0, nothing is written
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTA), vector1(0x80)),
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTB), vector1(0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x00, 0x00)),
+            //unknown
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTA), vector1(0x00)),
+            I2cTransaction::write_read(0x40, vector1(IOCON_BANK1_PORTB), vector1(0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Iocon as u8), vector2(0x12, 0x00)),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut mcp: MCP23017<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            MCP23017::new(i2c.clone(), 0x40);
+
+        assert_eq!(Bank::Bank0, mcp.recover_addressing(true).unwrap());
+        assert_eq!(Bank::Bank1, mcp.recover_addressing(true).unwrap());
+        assert_eq!(
+            Error::Protocol(ProtocolError::UnknownAddressing),
+            mcp.recover_addressing(true).unwrap_err()
+        );
+        assert_eq!(
+            Error::Protocol(ProtocolError::UnknownAddressing),
+            mcp.recover_addressing(false).unwrap_err()
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_toggle_pin() {
+        let expectations = [
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x04)),
+            I2cTransaction::write(0x40, vector3(Register::Olat as u8, 0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Olat as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Olat as u8, 0x20, 0x00)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
         let mut mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
-            state: core::marker::PhantomData::<InputReady>,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
+            state: core::marker::PhantomData::<OutputReady>,
         };
-        let result = mcp.get_interrupted_pin(MyPort::Porta);
 
-        assert_eq!(None, result);
+        mcp.toggle_pin(MyPort::Portb, PinNumber::Pin2).unwrap();
+        mcp.enable_output_cache().unwrap();
+        mcp.toggle_pin(MyPort::Porta, PinNumber::Pin5).unwrap();
+
         //finalize execution
         i2c.done();
     }
 
     #[test]
-    fn test_get_interrupted_pin_success() {
+    fn test_interrupt_manager_into_slice() {
         let expectations = [
-            //get_interrupted_pin (read_config)
-            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x00, 0x80)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intf as u8), vector2(0x03, 0x01)),
+            I2cTransaction::write_read(0x40, vector1(Register::Intcap as u8), vector2(0x02, 0x01)),
         ];
 
         let mut i2c = I2cMock::new(&expectations);
-        let mut mcp = MCP23017 {
+        let mcp = MCP23017 {
             i2c: i2c.clone(),
             address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            spurious_interrupts: 0,
+            output_inversion: 0,
+            staged_interrupts: 0,
+            post_write_verify: PostWriteVerify::Off,
+            write_corrections: 0,
+            output_cache: None,
             state: core::marker::PhantomData::<InputReady>,
         };
-        let result = mcp.get_interrupted_pin(MyPort::Portb);
+        let mut manager = InterruptManager::new(mcp);
+        let empty = InterruptEvent {
+            port: MyPort::Porta,
+            pin: PinNumber::Pin0,
+            level: PinSet::Low,
+        };
+        let mut events = [empty; 2];
+
+        assert_eq!(2, manager.on_interrupt_into(&mut events).unwrap());
+        assert_eq!(
+            [
+                empty,
+                InterruptEvent {
+                    port: MyPort::Porta,
+                    pin: PinNumber::Pin1,
+                    level: PinSet::High,
+                },
+            ],
+            events
+        );
 
-        assert_eq!(Some(PinNumber::Pin7), result);
         //finalize execution
+        manager.release();
         i2c.done();
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_config_serde_round_trip() {
+        let config = ConfigBuilder::new()
+            .with_iodir(IodirPair(0x00ff))
+            .with_gppu(0x0f0f)
+            .with_iocon(0x40);
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            config,
+            serde_json::from_str::<ConfigBuilder>(&json).unwrap()
+        );
+
+        let snapshot = RegisterSnapshot {
+            olatb: 0x80,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            snapshot,
+            serde_json::from_str::<RegisterSnapshot>(&json).unwrap()
+        );
+
+        let json = serde_json::to_string(&(MyPort::Portb, PinNumber::Pin3, PinSet::High)).unwrap();
+        assert_eq!(
+            (MyPort::Portb, PinNumber::Pin3, PinSet::High),
+            serde_json::from_str(&json).unwrap()
+        );
+    }
 }
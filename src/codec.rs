@@ -0,0 +1,176 @@
+#![allow(dead_code, unused)]
+
+use crate::prelude::{MyPort, PinNumber, PinSet};
+use crate::registers::{bit_clear, bit_level, bit_set};
+
+/**
+ * Function that splits a 16 bit register pair into its port bytes,
+ * index 0 holds Port A and index 1 holds Port B (LittleEndian)
+ */
+#[inline]
+pub fn split_ports(value: u16) -> [u8; 2] {
+    value.to_le_bytes()
+}
+
+/**
+ * Function that joins the port bytes of a register pair into a 16 bit value,
+ * index 0 must hold Port A and index 1 must hold Port B (LittleEndian)
+ */
+#[inline]
+pub fn join_ports(ports: [u8; 2]) -> u16 {
+    u16::from_le_bytes(ports)
+}
+
+/**
+ * Function that returns the byte belonging to the given port from a 16 bit register pair
+ */
+#[inline]
+pub fn port_byte(value: u16, port: MyPort) -> u8 {
+    split_ports(value)[port as usize]
+}
+
+/**
+ * Function that replaces the byte belonging to the given port in a 16 bit register pair
+ */
+#[inline]
+pub fn set_port_byte(value: u16, port: MyPort, byte: u8) -> u16 {
+    let mut ports = split_ports(value);
+    ports[port as usize] = byte;
+    join_ports(ports)
+}
+
+macro_rules! register_pair {
+    ($pair_name: ident, $register: literal) => {
+        #[doc = concat!("Typed value of the ", $register, " register pair, Port A in the low byte and Port B in the high byte")]
+        #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $pair_name(pub u16);
+
+        impl $pair_name {
+            /**
+             * Function used to get the Port A byte
+             */
+            #[inline]
+            pub fn porta(&self) -> u8 {
+                port_byte(self.0, MyPort::Porta)
+            }
+
+            /**
+             * Function used to get the Port B byte
+             */
+            #[inline]
+            pub fn portb(&self) -> u8 {
+                port_byte(self.0, MyPort::Portb)
+            }
+
+            /**
+             * Function used to get the byte of the given port
+             */
+            #[inline]
+            pub fn port(&self, port: MyPort) -> u8 {
+                port_byte(self.0, port)
+            }
+
+            /**
+             * Function used to get the bit of a pin as a pin level
+             */
+            #[inline]
+            pub fn pin(&self, port: MyPort, pin: PinNumber) -> PinSet {
+                bit_level(self.port(port), pin)
+            }
+
+            /**
+             * Function used to replace the byte of the given port
+             */
+            #[inline]
+            pub fn with_port(self, port: MyPort, byte: u8) -> Self {
+                $pair_name(set_port_byte(self.0, port, byte))
+            }
+
+            /**
+             * Function used to set or clear the bit of a pin
+             */
+            #[inline]
+            pub fn with_pin(self, port: MyPort, pin: PinNumber, value: PinSet) -> Self {
+                let byte = match value {
+                    PinSet::High => bit_set(self.port(port), pin),
+                    PinSet::Low => bit_clear(self.port(port), pin),
+                };
+                self.with_port(port, byte)
+            }
+        }
+
+        impl From<u16> for $pair_name {
+            fn from(value: u16) -> Self {
+                $pair_name(value)
+            }
+        }
+
+        impl From<$pair_name> for u16 {
+            fn from(value: $pair_name) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+register_pair!(IodirPair, "IODIR");
+register_pair!(IpolPair, "IPOL");
+register_pair!(GpintenPair, "GPINTEN");
+register_pair!(DefvalPair, "DEFVAL");
+register_pair!(IntconPair, "INTCON");
+register_pair!(GppuPair, "GPPU");
+register_pair!(IntcapPair, "INTCAP");
+register_pair!(GpioPair, "GPIO");
+register_pair!(OlatPair, "OLAT");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    #[test]
+    fn test_split_ports() {
+        assert_eq!([0xaa, 0xbb], split_ports(0xbbaa));
+        assert_eq!([0x00, 0xff], split_ports(0xff00));
+    }
+
+    #[test]
+    fn test_join_ports() {
+        assert_eq!(0xbbaa, join_ports([0xaa, 0xbb]));
+        assert_eq!(0x00ff, join_ports([0xff, 0x00]));
+    }
+
+    #[test]
+    fn test_port_byte() {
+        assert_eq!(0xaa, port_byte(0xbbaa, MyPort::Porta));
+        assert_eq!(0xbb, port_byte(0xbbaa, MyPort::Portb));
+    }
+
+    #[test]
+    fn test_set_port_byte() {
+        assert_eq!(0xbb12, set_port_byte(0xbbaa, MyPort::Porta, 0x12));
+        assert_eq!(0x12aa, set_port_byte(0xbbaa, MyPort::Portb, 0x12));
+    }
+
+    #[test]
+    fn test_register_pair() {
+        let pair = GpioPair::from(0xbbaa);
+
+        assert_eq!(0xaa, pair.porta());
+        assert_eq!(0xbb, pair.portb());
+        assert_eq!(0xbb, pair.port(MyPort::Portb));
+        assert_eq!(PinSet::High, pair.pin(MyPort::Porta, PinNumber::Pin1));
+        assert_eq!(PinSet::Low, pair.pin(MyPort::Porta, PinNumber::Pin0));
+        assert_eq!(
+            GpioPair(0xbbab),
+            pair.with_pin(MyPort::Porta, PinNumber::Pin0, PinSet::High)
+        );
+        assert_eq!(
+            GpioPair(0x3baa),
+            pair.with_pin(MyPort::Portb, PinNumber::Pin7, PinSet::Low)
+        );
+        assert_eq!(GpioPair(0x12aa), pair.with_port(MyPort::Portb, 0x12));
+        assert_eq!(0xbbaa_u16, u16::from(pair));
+    }
+}
@@ -17,10 +17,20 @@ pub mod portmode;
 #[cfg(feature = "portmode")]
 pub use crate::portmode::*;
 
+mod codec;
+pub mod polling;
 pub mod prelude;
 mod registers;
+#[cfg(all(feature = "chipmode", feature = "isr-shared", not(feature = "async")))]
+pub mod shared;
 
-use prelude::SlaveAddressing;
+use prelude::*;
+use registers::*;
+
+#[cfg(not(feature = "async"))]
+use embedded_hal::i2c::I2c;
+#[cfg(feature = "async")]
+use embedded_hal_async::i2c::I2c;
 
 /////// Support functions
 
@@ -41,12 +51,146 @@ pub fn convert_slave_address(a0: SlaveAddressing, a1: SlaveAddressing, a2: Slave
     }
 }
 
+/**
+ * Function that probes every address the chip can be strapped to and reports the devices that answer
+ */
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), keep_self,),
+    async(feature = "async", keep_self)
+)]
+pub async fn enumerate_bus<I2C: I2c>(i2c: &mut I2C) -> heapless::Vec<DeviceInfo, 8> {
+    let mut devices = heapless::Vec::new();
+
+    for address in 0x20..=0x27 {
+        if i2c.write(address, &[Register::Iocon as u8]).await.is_err() {
+            continue;
+        }
+
+        let mut bank1 = [0; 2];
+        let porta = i2c
+            .write_read(address, &[IOCON_BANK1_PORTA], &mut bank1[0..1])
+            .await;
+        let portb = i2c
+            .write_read(address, &[IOCON_BANK1_PORTB], &mut bank1[1..2])
+            .await;
+        let mut bank0 = [0; 2];
+        let iocon_readable = i2c
+            .write_read(address, &[Register::Iocon as u8], &mut bank0)
+            .await
+            .is_ok();
+
+        let bank = if iocon_readable && porta.is_ok() && portb.is_ok() {
+            bank_from_iocon(bank1, bank0)
+        } else {
+            None
+        };
+
+        // Capacity matches the address range, push cannot fail
+        let _ = devices.push(DeviceInfo {
+            address,
+            iocon_readable,
+            bank,
+        });
+    }
+
+    devices
+}
+
 /////// Tests
 
 #[cfg(test)]
 mod tests {
     use super::*;
     extern crate std;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+
+    #[test]
+    fn test_enumerate_bus() {
+        let mut expectations = vec![];
+        for address in 0x20..=0x27 {
+            match address {
+                0x20 => {
+                    expectations.push(I2cTransaction::write(address, vec![0x0A]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x05], vec![0x00]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x15], vec![0x00]));
+                    expectations.push(I2cTransaction::write_read(
+                        address,
+                        vec![0x0A],
+                        vec![0x40, 0x40],
+                    ));
+                }
+                0x22 => {
+                    // bank 0 with GPINTENB = OLATB = 0x80
+                    expectations.push(I2cTransaction::write(address, vec![0x0A]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x05], vec![0x80]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x15], vec![0x80]));
+                    expectations.push(I2cTransaction::write_read(
+                        address,
+                        vec![0x0A],
+                        vec![0x40, 0x40],
+                    ));
+                }
+                0x23 => {
+                    // ambiguous: bank 1 with OLATA = 0 or bank 0 with IOCON = 0 and
+                    // GPINTENB = OLATB = 0x80
+                    expectations.push(I2cTransaction::write(address, vec![0x0A]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x05], vec![0x80]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x15], vec![0x80]));
+                    expectations.push(I2cTransaction::write_read(
+                        address,
+                        vec![0x0A],
+                        vec![0x00, 0x00],
+                    ));
+                }
+                0x25 => {
+                    expectations.push(I2cTransaction::write(address, vec![0x0A]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x05], vec![0x80]));
+                    expectations.push(I2cTransaction::write_read(address, vec![0x15], vec![0x80]));
+                    expectations.push(I2cTransaction::write_read(
+                        address,
+                        vec![0x0A],
+                        vec![0x12, 0x00],
+                    ));
+                }
+                _ => expectations.push(
+                    I2cTransaction::write(address, vec![0x0A])
+                        .with_error(embedded_hal::i2c::ErrorKind::Other),
+                ),
+            }
+        }
+        let mut i2c = I2cMock::new(&expectations);
+
+        let devices = enumerate_bus(&mut i2c);
+
+        assert_eq!(
+            &[
+                DeviceInfo {
+                    address: 0x20,
+                    iocon_readable: true,
+                    bank: Some(Bank::Bank0),
+                },
+                DeviceInfo {
+                    address: 0x22,
+                    iocon_readable: true,
+                    bank: Some(Bank::Bank0),
+                },
+                DeviceInfo {
+                    address: 0x23,
+                    iocon_readable: true,
+                    bank: None,
+                },
+                DeviceInfo {
+                    address: 0x25,
+                    iocon_readable: true,
+                    bank: Some(Bank::Bank1),
+                },
+            ],
+            devices.as_slice()
+        );
+        //finalize execution
+        i2c.done();
+    }
 
     #[test]
     fn test_convert_slave_address() {
@@ -14,19 +14,22 @@ use PinNumber::Pin5 as pin5;
 use PinNumber::Pin6 as pin6;
 use PinNumber::Pin7 as pin7;
 
-use byteorder::{ByteOrder, LittleEndian};
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
 #[cfg(not(feature = "async"))]
 use embedded_hal::i2c::I2c;
 #[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+#[cfg(feature = "async")]
 use embedded_hal_async::i2c::I2c;
 
 #[maybe_async_cfg::maybe(
     sync(cfg(not(feature = "async")), keep_self,),
     async(feature = "async", keep_self)
 )]
-trait Regread {
-    async fn read_config(&mut self, register: Register) -> Result<u8, Error>;
-    async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error>;
+trait Regread<E> {
+    async fn read_config(&mut self, register: Register) -> Result<u8, Error<E>>;
+    async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error<E>>;
 }
 
 macro_rules! define_pin {
@@ -35,6 +38,7 @@ macro_rules! define_pin {
         pub struct $pin_name<I2C, State = Configuring> {
             i2c: I2C,
             address: u8,
+            read_style: ReadStyle,
             port: MyPort,
             pin: PinNumber,
             state: core::marker::PhantomData<State>,
@@ -56,11 +60,20 @@ macro_rules! create_pin {
                 $pin_name {
                     i2c,
                     address,
+                    read_style: Default::default(),
                     port: $my_port,
                     pin: $my_pinnumber,
                     state: Default::default(),
                 }
             }
+
+            /**
+             * Function used to choose how register reads are issued on the bus
+             */
+            #[inline]
+            pub fn set_read_style(&mut self, read_style: ReadStyle) {
+                self.read_style = read_style;
+            }
         }
     };
 }
@@ -71,7 +84,7 @@ macro_rules! read_write {
                                     sync(cfg(not(feature = "async")), self = $port_literal,),
                                     async(feature = "async", keep_self)
                                 )]
-        impl<I2C, E, State> Regread for $pin_name<I2C, State>
+        impl<I2C, E, State> Regread<E> for $pin_name<I2C, State>
         where
             I2C: I2c<Error = E>,
         {
@@ -79,13 +92,26 @@ macro_rules! read_write {
              * Private function used to read the chip registers using i2c
              */
             #[inline]
-            async fn read_config(&mut self, register: Register) -> Result<u8, Error> {
+            async fn read_config(&mut self, register: Register) -> Result<u8, Error<E>> {
                 let register_address = register as u8 | self.port as u8;
                 let mut rx_buffer: [u8; 1] = [0; 1];
-                self.i2c
-                .write_read(self.address, &[register_address], &mut rx_buffer)
-                .await
-                .map_err(i2c_comm_error)?;
+                match self.read_style {
+                    ReadStyle::RepeatedStart => self
+                        .i2c
+                        .write_read(self.address, &[register_address], &mut rx_buffer)
+                        .await
+                        .map_err(i2c_comm_error)?,
+                    ReadStyle::StopThenRead => {
+                        self.i2c
+                            .write(self.address, &[register_address])
+                            .await
+                            .map_err(i2c_comm_error)?;
+                        self.i2c
+                            .read(self.address, &mut rx_buffer)
+                            .await
+                            .map_err(i2c_comm_error)?;
+                    }
+                }
 
                 Ok(rx_buffer[0])
             }
@@ -94,8 +120,10 @@ macro_rules! read_write {
              * Private function used to write the chip registers using i2c
              */
             #[inline]
-            async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error> {
+            async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error<E>> {
                 let register_address = register as u8 | self.port as u8;
+                #[cfg(feature = "strict")]
+                check_reserved_bits(register, value)?;
 
                 self.i2c
                     .write(self.address, &[register_address, value])
@@ -118,11 +146,62 @@ macro_rules! set_as {
         where
             I2C: I2c<Error = E>,
         {
+            /**
+             * Function used to create a new handler once the device answers, the probe read
+             * is retried every millisecond until timeout_ms expires
+             */
+            #[inline]
+            pub async fn new_wait_ready<D: DelayNs>(
+                i2c: I2C,
+                address: u8,
+                delay: &mut D,
+                timeout_ms: u32,
+            ) -> Result<Self, Error<E>> {
+                let mut handler = Self::new(i2c, address);
+                let mut elapsed_ms = 0;
+
+                loop {
+                    match handler.read_config(Register::Iocon).await {
+                        Ok(_) => return Ok(handler),
+                        Err(e) if elapsed_ms >= timeout_ms => return Err(e),
+                        Err(_) => {}
+                    }
+                    delay.delay_ms(1).await;
+                    elapsed_ms += 1;
+                }
+            }
+
+            /**
+             * Function used to configure the pin as input following a preset, the interrupt is
+             * enabled last so no event fires while half configured
+             */
+            #[inline]
+            pub async fn apply_preset(self, preset: Preset) -> Result<$pin_name<I2C, InputReady>, Error<E>> {
+                let input = self.set_as_input().await?.set_pull(PinSet::High).await?;
+
+                let input = match preset {
+                    Preset::PushButtonActiveLow | Preset::EncoderChannel => {
+                        input.set_interrupt_on(InterruptOn::PinChange).await?
+                    }
+                    Preset::LimitSwitchNO => {
+                        input
+                            .set_interrupt_on(InterruptOn::ChangeFromRegister)
+                            .await?
+                            .set_interrupt_compare(PinSet::High)
+                            .await?
+                    }
+                };
+
+                let mut input = input.ready();
+                input.enable_interrupt().await?;
+                Ok(input)
+            }
+
             /**
              * Function used to set the chip/port/pin as input
              */
             #[inline]
-            pub async fn set_as_input(mut self) -> Result<$pin_name<I2C, InputConfiguring>, Error> {
+            pub async fn set_as_input(mut self) -> Result<$pin_name<I2C, InputConfiguring>, Error<E>> {
                 let result = self.read_config(Register::Iodir).await?;
                 self.write_config(Register::Iodir, bit_set(result, self.pin))
                     .await?;
@@ -130,6 +209,7 @@ macro_rules! set_as {
                 Ok($pin_name {
                     i2c: self.i2c,
                     address: self.address,
+                    read_style: self.read_style,
                     port: self.port,
                     pin: self.pin,
                     state: core::marker::PhantomData::<InputConfiguring>,
@@ -140,7 +220,7 @@ macro_rules! set_as {
              * Function used to set the chip/port/pin as output
              */
             #[inline]
-            pub async fn set_as_output(mut self) -> Result<$pin_name<I2C, OutputReady>, Error> {
+            pub async fn set_as_output(mut self) -> Result<$pin_name<I2C, OutputReady>, Error<E>> {
                 let result = self.read_config(Register::Iodir).await?;
                 self.write_config(Register::Iodir, bit_clear(result, self.pin))
                     .await?;
@@ -148,6 +228,7 @@ macro_rules! set_as {
                 Ok($pin_name {
                     i2c: self.i2c,
                     address: self.address,
+                    read_style: self.read_style,
                     port: self.port,
                     pin: self.pin,
                     state: core::marker::PhantomData::<OutputReady>,
@@ -171,7 +252,7 @@ macro_rules! outputready {
              * Function used to write the output value to be set on chip/port/pin
              */
             #[inline]
-            pub async fn write(&mut self, value: PinSet) -> Result<(), Error> {
+            pub async fn write(&mut self, value: PinSet) -> Result<(), Error<E>> {
                 let mut result = self.read_config(Register::Gpio).await?;
 
                 result = match value {
@@ -179,10 +260,137 @@ macro_rules! outputready {
                     PinSet::Low => bit_clear(result, self.pin),
                 };
 
-                self.write_config(Register::Gpio, result).await.map_err(i2c_comm_error)?;
+                self.write_config(Register::Gpio, result).await?;
 
                 Ok(())
             }
+
+            /**
+             * Function used to write the output value with a read-modify-write of the output
+             * latch (OLAT), so externally pulled pins of the port are not copied into the latch
+             */
+            #[inline]
+            pub async fn write_latch(&mut self, value: PinSet) -> Result<(), Error<E>> {
+                let result = self.read_config(Register::Olat).await?;
+
+                let result = match value {
+                    PinSet::High => bit_set(result, self.pin),
+                    PinSet::Low => bit_clear(result, self.pin),
+                };
+
+                self.write_config(Register::Olat, result).await
+            }
+
+            /**
+             * Function used to invert the output value with a read-modify-write of the output
+             * latch (OLAT)
+             */
+            #[inline]
+            pub async fn toggle(&mut self) -> Result<(), Error<E>> {
+                let result = self.read_config(Register::Olat).await?;
+
+                self.write_config(Register::Olat, result ^ pin_number_to_mask(self.pin) as u8)
+                    .await
+            }
+
+            /**
+             * Function used to read the output latch (OLAT) of the pin, unlike GPIO it is not
+             * affected by external loading of the pin
+             */
+            #[inline]
+            pub async fn read_output_latch(&mut self) -> Result<PinSet, Error<E>> {
+                let result = self.read_config(Register::Olat).await?;
+
+                Ok(bit_level(result, self.pin))
+            }
+
+            /**
+             * Function used to verify the output by comparing its latch (OLAT) with the pin level (GPIO)
+             */
+            #[inline]
+            pub async fn verify(&mut self) -> Result<PinVerify, Error<E>> {
+                let iodir = self.read_config(Register::Iodir).await?;
+                let olat = self.read_config(Register::Olat).await?;
+                let gpio = self.read_config(Register::Gpio).await?;
+
+                Ok(classify_output(iodir, olat, gpio, self.pin))
+            }
+
+            /**
+             * Function used to turn the output into a ready input, only the pin direction
+             * (IODIR) is changed
+             */
+            #[inline]
+            pub async fn into_input(mut self) -> Result<$pin_name<I2C, InputReady>, Error<E>> {
+                let result = self.read_config(Register::Iodir).await?;
+                self.write_config(Register::Iodir, bit_set(result, self.pin))
+                    .await?;
+
+                Ok($pin_name {
+                    i2c: self.i2c,
+                    address: self.address,
+                    read_style: self.read_style,
+                    port: self.port,
+                    pin: self.pin,
+                    state: core::marker::PhantomData::<InputReady>,
+                })
+            }
+        }
+
+        #[cfg(not(feature = "async"))]
+        impl<I2C, E> embedded_hal::digital::ErrorType for $pin_name<I2C, OutputReady>
+        where
+            I2C: I2c<Error = E>,
+            E: core::fmt::Debug,
+        {
+            type Error = Error<E>;
+        }
+
+        /**
+         * Function implements the OutputPin trait, the value is written to the latch (OLAT)
+         * so it matches the state reported by StatefulOutputPin
+         */
+        #[cfg(not(feature = "async"))]
+        impl<I2C, E> embedded_hal::digital::OutputPin for $pin_name<I2C, OutputReady>
+        where
+            I2C: I2c<Error = E>,
+            E: core::fmt::Debug,
+        {
+            #[inline]
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                self.write_latch(PinSet::Low)
+            }
+
+            #[inline]
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                self.write_latch(PinSet::High)
+            }
+        }
+
+        /**
+         * Function implements the StatefulOutputPin trait, the state is read from the latch (OLAT)
+         */
+        #[cfg(not(feature = "async"))]
+        impl<I2C, E> embedded_hal::digital::StatefulOutputPin for $pin_name<I2C, OutputReady>
+        where
+            I2C: I2c<Error = E>,
+            E: core::fmt::Debug,
+        {
+            #[inline]
+            fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+                let olat = self.read_config(Register::Olat)?;
+                Ok(bit_level(olat, self.pin) == PinSet::High)
+            }
+
+            #[inline]
+            fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+                Ok(!self.is_set_high()?)
+            }
+
+            #[inline]
+            fn toggle(&mut self) -> Result<(), Self::Error> {
+                $pin_name::toggle(self)
+            }
         }
     };
 }
@@ -201,17 +409,47 @@ macro_rules! inputready {
              * Function used to read the input
              */
             #[inline]
-            pub async fn read(&mut self) -> Result<u8, Error> {
+            pub async fn read(&mut self) -> Result<u8, Error<E>> {
                 let mut result = self.read_config(Register::Gpio).await?;
 
                 Ok(bit_read(result, self.pin))
             }
 
+            /**
+             * Function used to read the input as a pin level
+             */
+            #[inline]
+            pub async fn read_level(&mut self) -> Result<PinSet, Error<E>> {
+                let result = self.read_config(Register::Gpio).await?;
+
+                Ok(bit_level(result, self.pin))
+            }
+
+            /**
+             * Function used to turn the input into an output, only the pin direction
+             * (IODIR) is changed so the latch (OLAT) value is driven
+             */
+            #[inline]
+            pub async fn into_output(mut self) -> Result<$pin_name<I2C, OutputReady>, Error<E>> {
+                let result = self.read_config(Register::Iodir).await?;
+                self.write_config(Register::Iodir, bit_clear(result, self.pin))
+                    .await?;
+
+                Ok($pin_name {
+                    i2c: self.i2c,
+                    address: self.address,
+                    read_style: self.read_style,
+                    port: self.port,
+                    pin: self.pin,
+                    state: core::marker::PhantomData::<OutputReady>,
+                })
+            }
+
             /**
              * Function used to disable the interrupt on the input
              */
             #[inline]
-            pub async fn disable_interrupt(&mut self) -> Result<(), Error> {
+            pub async fn disable_interrupt(&mut self) -> Result<(), Error<E>> {
                 let mut reg = self.read_config(Register::Gpinten).await?;
 
                 reg = bit_clear(reg, self.pin);
@@ -223,7 +461,7 @@ macro_rules! inputready {
              * Function used to enable the interrupt on the input
              */
             #[inline]
-            pub async fn enable_interrupt(&mut self) -> Result<(), Error> {
+            pub async fn enable_interrupt(&mut self) -> Result<(), Error<E>> {
                 let mut reg = self.read_config(Register::Gpinten).await?;
 
                 reg = bit_set(reg, self.pin);
@@ -231,13 +469,88 @@ macro_rules! inputready {
             }
 
             /**
-             * Function used to verify the interrupt on the input
+             * Function used to read the level of the input latched (INTCAP) when the last
+             * interrupt of its port fired, reading the capture clears the interrupt
+             */
+            #[inline]
+            pub async fn read_interrupt_capture(&mut self) -> Result<PinSet, Error<E>> {
+                let intcap = self.read_config(Register::Intcap).await?;
+
+                Ok(bit_level(intcap, self.pin))
+            }
+
+            /**
+             * Function used to get the pin of the port that fired the interrupt together with
+             * its level at interrupt time, the lowest pin is returned when several are flagged
+             * (see get_interrupted_pins), returns None if no pin is flagged
+             */
+            #[inline]
+            pub async fn get_interrupted_pin_level(
+                &mut self,
+            ) -> Result<Option<(PinNumber, PinSet)>, Error<E>> {
+                let intf = self.read_config(Register::Intf).await?;
+                let Some(pin) = PinFlags(intf).next() else {
+                    return Ok(None);
+                };
+                let intcap = self.read_config(Register::Intcap).await?;
+
+                Ok(Some((pin, bit_level(intcap, pin))))
+            }
+
+            /**
+             * Function used to get every pin of the port flagged in INTF
+             */
+            #[inline]
+            pub async fn get_interrupted_pins(&mut self) -> Result<PinFlags, Error<E>> {
+                Ok(PinFlags(self.read_config(Register::Intf).await?))
+            }
+
+            /**
+             * Function used to verify the interrupt on the input, communication errors are
+             * returned instead of being reported as no interrupt
+             */
+            #[inline]
+            pub async fn try_get_interrupted_pin(
+                &mut self,
+            ) -> Result<Option<PinNumber>, Error<E>> {
+                let pin_msk = self.read_config(Register::Intf).await?;
+
+                Ok(pin_mask_to_number(PinMask::from(pin_msk)))
+            }
+
+            /**
+             * Function used to verify the interrupt on the input, a communication error is
+             * reported as None (see try_get_interrupted_pin)
              */
             #[inline]
             pub async fn get_interrupted_pin(&mut self) -> Option<PinNumber> {
-                let pin_msk = self.read_config(Register::Intf).await.unwrap_or(0);
+                self.try_get_interrupted_pin().await.unwrap_or(None)
+            }
+        }
 
-                pin_mask_to_number(PinMask::from(pin_msk))
+        #[cfg(not(feature = "async"))]
+        impl<I2C, E> embedded_hal::digital::ErrorType for $pin_name<I2C, InputReady>
+        where
+            I2C: I2c<Error = E>,
+            E: core::fmt::Debug,
+        {
+            type Error = Error<E>;
+        }
+
+        #[cfg(not(feature = "async"))]
+        impl<I2C, E> embedded_hal::digital::InputPin for $pin_name<I2C, InputReady>
+        where
+            I2C: I2c<Error = E>,
+            E: core::fmt::Debug,
+        {
+            #[inline]
+            fn is_high(&mut self) -> Result<bool, Self::Error> {
+                Ok(self.read_level()? == PinSet::High)
+            }
+
+            #[inline]
+            fn is_low(&mut self) -> Result<bool, Self::Error> {
+                Ok(self.read_level()? == PinSet::Low)
             }
         }
     };
@@ -258,7 +571,7 @@ macro_rules! inputconfiguring {
              * Function used to set the pull on the input
              */
             #[inline]
-            pub async fn set_pull(mut self, pull: PinSet) -> Result<Self, Error> {
+            pub async fn set_pull(mut self, pull: PinSet) -> Result<Self, Error<E>> {
                 let mut reg = self.read_config(Register::Gppu).await?;
 
                 reg = match pull {
@@ -278,7 +591,7 @@ macro_rules! inputconfiguring {
             pub async fn set_interrupt_mirror(
                 mut self,
                 mirror: InterruptMirror,
-            ) -> Result<Self, Error> {
+            ) -> Result<Self, Error<E>> {
                 let mut reg = self.read_config(Register::Iocon).await?;
 
                 match mirror {
@@ -295,6 +608,39 @@ macro_rules! inputconfiguring {
                 Ok(self)
             }
 
+            /**
+             * Function used to set the output type of the INT pin (open-drain or push-pull
+             * with its active level)
+             */
+            #[inline]
+            pub async fn set_interrupt_output(
+                mut self,
+                output: InterruptOutput,
+            ) -> Result<Self, Error<E>> {
+                let reg = self.read_config(Register::Iocon).await?;
+
+                self.write_config(Register::Iocon, iocon_interrupt_output(reg, output))
+                    .await?;
+
+                Ok(self)
+            }
+
+            /**
+             * Function used to set the polarity (IPOL) of the input
+             */
+            #[inline]
+            pub async fn set_polarity(mut self, polarity: Polarity) -> Result<Self, Error<E>> {
+                let mut reg = self.read_config(Register::Ipol).await?;
+
+                reg = match polarity {
+                    Polarity::Normal => bit_clear(reg, self.pin),
+                    Polarity::Inverted => bit_set(reg, self.pin),
+                };
+
+                self.write_config(Register::Ipol, reg).await?;
+                Ok(self)
+            }
+
             /**
              * Function used to choose the pin as interrupt on the input
              */
@@ -302,7 +648,7 @@ macro_rules! inputconfiguring {
             pub async fn set_interrupt_on(
                 mut self,
                 interrupt_on: InterruptOn,
-            ) -> Result<Self, Error> {
+            ) -> Result<Self, Error<E>> {
                 let mut reg = self.read_config(Register::Intcon).await?;
 
                 reg = match interrupt_on {
@@ -321,11 +667,11 @@ macro_rules! inputconfiguring {
             pub async fn set_interrupt_compare(
                 mut self,
                 value: PinSet,
-            ) -> Result<Self, Error> {
+            ) -> Result<Self, Error<E>> {
                 let intcon = self.read_config(Register::Intcon).await?;
 
                 if bit_read(intcon, self.pin) != 1 {
-                    return Err(Error::InvalidInterruptSetting);
+                    return Err(Error::Protocol(ProtocolError::InvalidInterruptSetting));
                 }
 
                 let mut reg = self.read_config(Register::Defval).await?; //change only valid if intcon is set to 1
@@ -347,6 +693,7 @@ macro_rules! inputconfiguring {
                 $pin_name {
                     i2c: self.i2c,
                     address: self.address,
+                    read_style: self.read_style,
                     port: self.port,
                     pin: self.pin,
                     state: core::marker::PhantomData::<InputReady>,
@@ -484,6 +831,58 @@ outputready!(Pinb7, "Pinb7");
 inputconfiguring!(Pinb7, "Pinb7");
 inputready!(Pinb7, "Pinb7");
 
+/// All pins of one chip, each owning its own device on the shared bus (see split)
+#[derive(Debug)]
+pub struct Parts<I2C> {
+    pub a0: Pina0<I2C>,
+    pub a1: Pina1<I2C>,
+    pub a2: Pina2<I2C>,
+    pub a3: Pina3<I2C>,
+    pub a4: Pina4<I2C>,
+    pub a5: Pina5<I2C>,
+    pub a6: Pina6<I2C>,
+    pub a7: Pina7<I2C>,
+    pub b0: Pinb0<I2C>,
+    pub b1: Pinb1<I2C>,
+    pub b2: Pinb2<I2C>,
+    pub b3: Pinb3<I2C>,
+    pub b4: Pinb4<I2C>,
+    pub b5: Pinb5<I2C>,
+    pub b6: Pinb6<I2C>,
+    pub b7: Pinb7<I2C>,
+}
+
+impl<I2C, E> Parts<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function used to split a chip into its 16 pin handles, bus is called once per pin to
+     * create its device, e.g. || RefCellDevice::new(&i2c_ref_cell)
+     */
+    #[inline]
+    pub fn split<F: FnMut() -> I2C>(mut bus: F, address: u8) -> Self {
+        Parts {
+            a0: Pina0::new(bus(), address),
+            a1: Pina1::new(bus(), address),
+            a2: Pina2::new(bus(), address),
+            a3: Pina3::new(bus(), address),
+            a4: Pina4::new(bus(), address),
+            a5: Pina5::new(bus(), address),
+            a6: Pina6::new(bus(), address),
+            a7: Pina7::new(bus(), address),
+            b0: Pinb0::new(bus(), address),
+            b1: Pinb1::new(bus(), address),
+            b2: Pinb2::new(bus(), address),
+            b3: Pinb3::new(bus(), address),
+            b4: Pinb4::new(bus(), address),
+            b5: Pinb5::new(bus(), address),
+            b6: Pinb6::new(bus(), address),
+            b7: Pinb7::new(bus(), address),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate std;
@@ -549,6 +948,23 @@ mod tests {
         i2c.done();
     }
 
+    #[test]
+    fn test_read_config_stop_then_read_portb() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector1(Register::Gpio as u8 | MyPort::Portb as u8)),
+            I2cTransaction::read(0x40, vector1(0x5a)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pinb3: Pinb3<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            Pinb3::new(i2c.clone(), 0x40);
+        pinb3.set_read_style(ReadStyle::StopThenRead);
+        let result = pinb3.read_config(Register::Gpio);
+        assert_eq!(0x5a, result.unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
     #[test]
     fn test_write_pina() {
         let expectations = [
@@ -576,6 +992,7 @@ mod tests {
             Pina3 {
                 i2c: i2c.clone(),
                 address: 0x40,
+                read_style: ReadStyle::RepeatedStart,
                 port: MyPort::Porta,
                 pin: PinNumber::Pin3,
                 state: core::marker::PhantomData::<OutputReady>,
@@ -616,6 +1033,7 @@ mod tests {
             Pinb3 {
                 i2c: i2c.clone(),
                 address: 0x40,
+                read_style: ReadStyle::RepeatedStart,
                 port: MyPort::Portb,
                 pin: PinNumber::Pin3,
                 state: core::marker::PhantomData::<OutputReady>,
@@ -628,6 +1046,525 @@ mod tests {
         //finalize execution
         i2c.done();
     }
+
+    #[test]
+    fn test_verify_pina() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Porta as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Porta as u8),
+                vector1(0b00001000),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+                vector1(0b00001000),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pina3: Pina3<embedded_hal_mock::common::Generic<I2cTransaction>, OutputReady> =
+            Pina3 {
+                i2c: i2c.clone(),
+                address: 0x40,
+                read_style: ReadStyle::RepeatedStart,
+                port: MyPort::Porta,
+                pin: PinNumber::Pin3,
+                state: core::marker::PhantomData::<OutputReady>,
+            };
+
+        let result = pina3.verify();
+        assert_eq!(PinVerify::Driving, result.unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_new_wait_ready_pinb() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iocon as u8 | MyPort::Portb as u8),
+                vector1(0x00),
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iocon as u8 | MyPort::Portb as u8),
+                vector1(0x00),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let pinb3: Pinb3<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            Pinb3::new_wait_ready(
+                i2c.clone(),
+                0x40,
+                &mut embedded_hal_mock::eh1::delay::NoopDelay,
+                10,
+            )
+            .unwrap();
+
+        assert_eq!(PinNumber::Pin3, pinb3.pin);
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_level_pina() {
+        let expectations = [I2cTransaction::write_read(
+            0x40,
+            vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+            vector1(0xfb),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pina2 = Pina2 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            pin: PinNumber::Pin2,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        let result = pina2.read_level().unwrap();
+        assert_eq!(PinSet::Low, result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_stateful_output_pinb() {
+        use embedded_hal::digital::{OutputPin, StatefulOutputPin};
+
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Portb as u8, 0x20),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x20),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x20),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Portb as u8, 0x00),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pinb5 = Pinb5 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            pin: PinNumber::Pin5,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        pinb5.set_high().unwrap();
+        assert!(pinb5.is_set_high().unwrap());
+        pinb5.toggle().unwrap();
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_into_input_into_output_pina() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Porta as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Porta as u8, 0x40),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Porta as u8),
+                vector1(0xff),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Porta as u8, 0xbf),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Porta as u8),
+                vector1(0xbf),
+            )
+            .with_error(ErrorKind::Other),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let pina6 = Pina6 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            pin: PinNumber::Pin6,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        let pina6 = pina6.into_input().unwrap();
+        let pina6 = pina6.into_output().unwrap();
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            pina6.into_input().unwrap_err()
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_apply_preset_pinb() {
+        let rb = |register: Register| register as u8 | MyPort::Portb as u8;
+        let expectations = [
+            I2cTransaction::write_read(0x40, vector1(rb(Register::Iodir)), vector1(0x00)),
+            I2cTransaction::write(0x40, vector2(rb(Register::Iodir), 0x02)),
+            I2cTransaction::write_read(0x40, vector1(rb(Register::Gppu)), vector1(0x00)),
+            I2cTransaction::write(0x40, vector2(rb(Register::Gppu), 0x02)),
+            I2cTransaction::write_read(0x40, vector1(rb(Register::Intcon)), vector1(0x00)),
+            I2cTransaction::write(0x40, vector2(rb(Register::Intcon), 0x02)),
+            I2cTransaction::write_read(0x40, vector1(rb(Register::Intcon)), vector1(0x02)),
+            I2cTransaction::write_read(0x40, vector1(rb(Register::Defval)), vector1(0x00)),
+            I2cTransaction::write(0x40, vector2(rb(Register::Defval), 0x02)),
+            I2cTransaction::write_read(0x40, vector1(rb(Register::Gpinten)), vector1(0x00)),
+            I2cTransaction::write(0x40, vector2(rb(Register::Gpinten), 0x02)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let pinb1: Pinb1<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            Pinb1::new(i2c.clone(), 0x40);
+
+        let pinb1 = pinb1.apply_preset(Preset::LimitSwitchNO).unwrap();
+        assert_eq!(PinNumber::Pin1, pinb1.pin);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_input_pin_pinb() {
+        use embedded_hal::digital::InputPin;
+
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+                vector1(0x80),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+                vector1(0x80),
+            )
+            .with_error(ErrorKind::Other),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pinb7 = Pinb7 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            pin: PinNumber::Pin7,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        assert!(pinb7.is_high().unwrap());
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            pinb7.is_low().unwrap_err()
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_shared_bus_refcell_device() {
+        use core::cell::RefCell;
+        use embedded_hal_bus::i2c::RefCellDevice;
+
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Porta as u8),
+                vector1(0xff),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Porta as u8, 0xfd),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Portb as u8),
+                vector1(0xff),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Portb as u8, 0xff),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Gpio as u8 | MyPort::Porta as u8, 0x02),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+                vector1(0x08),
+            ),
+        ];
+        let i2c_ref_cell = RefCell::new(I2cMock::new(&expectations));
+
+        {
+            let mut pina1 = Pina1::new(RefCellDevice::new(&i2c_ref_cell), 0x40)
+                .set_as_output()
+                .unwrap();
+            let mut pinb3 = Pinb3::new(RefCellDevice::new(&i2c_ref_cell), 0x40)
+                .set_as_input()
+                .unwrap()
+                .ready();
+
+            pina1.write(PinSet::High).unwrap();
+            assert_eq!(1, pinb3.read().unwrap());
+        }
+
+        //finalize execution
+        i2c_ref_cell.into_inner().done();
+    }
+
+    #[test]
+    fn test_split() {
+        use core::cell::RefCell;
+        use embedded_hal_bus::i2c::RefCellDevice;
+
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Portb as u8),
+                vector1(0xff),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Portb as u8, 0x7f),
+            ),
+        ];
+        let i2c_ref_cell = RefCell::new(I2cMock::new(&expectations));
+
+        {
+            let parts = Parts::split(|| RefCellDevice::new(&i2c_ref_cell), 0x40);
+            assert_eq!(PinNumber::Pin0, parts.a0.pin);
+            assert_eq!(MyPort::Porta, parts.a7.port);
+            assert_eq!(MyPort::Portb, parts.b0.port);
+            assert_eq!(PinNumber::Pin7, parts.b7.pin);
+
+            let pinb7 = parts.b7.set_as_output().unwrap();
+            assert_eq!(0x40, pinb7.address);
+        }
+
+        //finalize execution
+        i2c_ref_cell.into_inner().done();
+    }
+
+    #[test]
+    fn test_set_polarity_pina() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Ipol as u8 | MyPort::Porta as u8),
+                vector1(0xff),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Ipol as u8 | MyPort::Porta as u8, 0xfe),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let pina0 = Pina0 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            pin: PinNumber::Pin0,
+            state: core::marker::PhantomData::<InputConfiguring>,
+        };
+
+        pina0.set_polarity(Polarity::Normal).unwrap();
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_interrupt_capture_pinb() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intcap as u8 | MyPort::Portb as u8),
+                vector1(0x20),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intf as u8 | MyPort::Portb as u8),
+                vector1(0x00),
+            ),
+            //two pins flagged
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intf as u8 | MyPort::Portb as u8),
+                vector1(0x0a),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intcap as u8 | MyPort::Portb as u8),
+                vector1(0x02),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pinb5 = Pinb5 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            pin: PinNumber::Pin5,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        assert_eq!(PinSet::High, pinb5.read_interrupt_capture().unwrap());
+        assert_eq!(None, pinb5.get_interrupted_pin_level().unwrap());
+        assert_eq!(
+            Some((PinNumber::Pin1, PinSet::High)),
+            pinb5.get_interrupted_pin_level().unwrap()
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_try_get_interrupted_pin_pina() {
+        let expectations = [I2cTransaction::write_read(
+            0x40,
+            vector1(Register::Intf as u8 | MyPort::Porta as u8),
+            vector1(0x00),
+        )
+        .with_error(embedded_hal::i2c::ErrorKind::Other)];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pina1 = Pina1 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            pin: PinNumber::Pin1,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        let result = pina1.try_get_interrupted_pin().unwrap_err();
+        assert_eq!(Error::Transport(ErrorKind::Other), result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_latch_pinb() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x01),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Portb as u8, 0x09),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x09),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pinb3 = Pinb3 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            pin: PinNumber::Pin3,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        pinb3.write_latch(PinSet::High).unwrap();
+        assert_eq!(PinSet::High, pinb3.read_output_latch().unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_toggle_pina() {
+        use embedded_hal::digital::StatefulOutputPin;
+
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Porta as u8),
+                vector1(0x10),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Porta as u8, 0x14),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Porta as u8),
+                vector1(0x14),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Porta as u8, 0x10),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pina2 = Pina2 {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            pin: PinNumber::Pin2,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        pina2.toggle().unwrap();
+        StatefulOutputPin::toggle(&mut pina2).unwrap();
+
+        //finalize execution
+        i2c.done();
+    }
 }
 // use crate::PinMask;
 
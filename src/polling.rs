@@ -0,0 +1,199 @@
+use crate::prelude::PinSet;
+
+/// Controller that adapts the input polling period to the recent activity,
+/// backing off exponentially while idle and returning to the minimum period on changes
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptivePoll {
+    min_us: u32,
+    max_us: u32,
+    current_us: u32,
+    last_sample: Option<u16>,
+}
+
+impl AdaptivePoll {
+    /**
+     * Function used to create a new controller bounded by min_us and max_us (microseconds)
+     */
+    #[inline]
+    pub fn new(min_us: u32, max_us: u32) -> Self {
+        let max_us = max_us.max(min_us);
+        AdaptivePoll {
+            min_us,
+            max_us,
+            current_us: min_us,
+            last_sample: None,
+        }
+    }
+
+    /**
+     * Function used to feed the result of the last poll, a change brings the period
+     * back to the minimum while an idle poll doubles it up to the maximum (a zero
+     * period steps to 1 microsecond first, so min_us = 0 still backs off)
+     */
+    #[inline]
+    pub fn update(&mut self, changed: bool) {
+        self.current_us = if changed {
+            self.min_us
+        } else {
+            self.current_us
+                .saturating_mul(2)
+                .max(1)
+                .clamp(self.min_us, self.max_us)
+        };
+    }
+
+    /**
+     * Function used to feed the value read on the last poll, returns whether it changed
+     */
+    #[inline]
+    pub fn observe(&mut self, sample: u16) -> bool {
+        let changed = self.last_sample.is_some_and(|last| last != sample);
+        self.last_sample = Some(sample);
+        self.update(changed);
+        changed
+    }
+
+    /**
+     * Function used to get the time to wait before the next poll, in microseconds
+     */
+    #[inline]
+    pub fn next_poll_in(&self) -> u32 {
+        self.current_us
+    }
+}
+
+/// Duty cycle estimator fed with periodic samples of an input, reporting the share of
+/// High samples over a window of window samples, scaled to resolution steps
+#[derive(Debug, Clone, PartialEq)]
+pub struct DutySampler {
+    window: u16,
+    resolution: u16,
+    samples: u16,
+    high: u16,
+}
+
+impl DutySampler {
+    /**
+     * Function used to create a new estimator, a window of 0 samples is treated as 1
+     */
+    #[inline]
+    pub fn new(window: u16, resolution: u16) -> Self {
+        DutySampler {
+            window: window.max(1),
+            resolution,
+            samples: 0,
+            high: 0,
+        }
+    }
+
+    /**
+     * Function used to feed a sample, returns the duty cycle (0..=resolution) once the
+     * window is complete and starts a new window
+     */
+    #[inline]
+    pub fn observe(&mut self, level: PinSet) -> Option<u16> {
+        self.samples += 1;
+        if level == PinSet::High {
+            self.high += 1;
+        }
+        if self.samples < self.window {
+            return None;
+        }
+
+        let duty = u32::from(self.high) * u32::from(self.resolution) / u32::from(self.window);
+        self.reset();
+        Some(duty as u16)
+    }
+
+    /**
+     * Function used to discard the samples of the current window
+     */
+    #[inline]
+    pub fn reset(&mut self) {
+        self.samples = 0;
+        self.high = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    #[test]
+    fn test_new() {
+        let poll = AdaptivePoll::new(1_000, 64_000);
+
+        assert_eq!(1_000, poll.next_poll_in());
+    }
+
+    #[test]
+    fn test_update_backoff() {
+        let mut poll = AdaptivePoll::new(1_000, 5_000);
+
+        poll.update(false);
+        assert_eq!(2_000, poll.next_poll_in());
+        poll.update(false);
+        assert_eq!(4_000, poll.next_poll_in());
+        poll.update(false);
+        assert_eq!(5_000, poll.next_poll_in());
+        poll.update(false);
+        assert_eq!(5_000, poll.next_poll_in());
+
+        poll.update(true);
+        assert_eq!(1_000, poll.next_poll_in());
+    }
+
+    #[test]
+    fn test_update_backoff_from_zero() {
+        let mut poll = AdaptivePoll::new(0, 4);
+
+        assert_eq!(0, poll.next_poll_in());
+        poll.update(false);
+        assert_eq!(1, poll.next_poll_in());
+        poll.update(false);
+        assert_eq!(2, poll.next_poll_in());
+        poll.update(false);
+        poll.update(false);
+        assert_eq!(4, poll.next_poll_in());
+
+        poll.update(true);
+        assert_eq!(0, poll.next_poll_in());
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut poll = AdaptivePoll::new(1_000, 64_000);
+
+        assert!(!poll.observe(0x00ff));
+        assert!(!poll.observe(0x00ff));
+        assert_eq!(4_000, poll.next_poll_in());
+        assert!(poll.observe(0x00fe));
+        assert_eq!(1_000, poll.next_poll_in());
+    }
+
+    #[test]
+    fn test_invalid_bounds() {
+        let mut poll = AdaptivePoll::new(1_000, 10);
+
+        poll.update(false);
+        assert_eq!(1_000, poll.next_poll_in());
+    }
+
+    #[test]
+    fn test_duty_sampler() {
+        let mut sampler = DutySampler::new(4, 100);
+
+        assert_eq!(None, sampler.observe(PinSet::High));
+        assert_eq!(None, sampler.observe(PinSet::Low));
+        assert_eq!(None, sampler.observe(PinSet::High));
+        assert_eq!(Some(75), sampler.observe(PinSet::High));
+        for _ in 0..3 {
+            assert_eq!(None, sampler.observe(PinSet::Low));
+        }
+        assert_eq!(Some(0), sampler.observe(PinSet::Low));
+
+        let mut sampler = DutySampler::new(0, 1000);
+        assert_eq!(Some(1000), sampler.observe(PinSet::High));
+    }
+}
@@ -5,19 +5,24 @@ use crate::registers::*;
 use MyPort::Porta as porta;
 use MyPort::Portb as portb;
 
-use byteorder::{ByteOrder, LittleEndian};
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
 #[cfg(not(feature = "async"))]
 use embedded_hal::i2c::I2c;
 #[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+#[cfg(feature = "async")]
 use embedded_hal_async::i2c::I2c;
 
+const PORT_FAILURE_THRESHOLD: u8 = 3; // Consecutive failures before the port is marked as failed
+
 #[maybe_async_cfg::maybe(
     sync(cfg(not(feature = "async")), keep_self,),
     async(feature = "async", keep_self)
 )]
-trait Regread {
-    async fn read_config(&mut self, register: Register) -> Result<u8, Error>;
-    async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error>;
+trait Regread<E> {
+    async fn read_config(&mut self, register: Register) -> Result<u8, Error<E>>;
+    async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error<E>>;
 }
 
 macro_rules! define_port {
@@ -26,7 +31,9 @@ macro_rules! define_port {
         pub struct $port_name<I2C, State = Configuring> {
             i2c: I2C,
             address: u8,
+            read_style: ReadStyle,
             port: MyPort,
+            failures: u8,
             state: core::marker::PhantomData<State>,
         }
     };
@@ -46,10 +53,60 @@ macro_rules! create_port {
                 $port_name {
                     i2c,
                     address,
+                    read_style: Default::default(),
                     port: $my_port,
+                    failures: 0,
                     state: Default::default(),
                 }
             }
+
+            /**
+             * Function used to choose how register reads are issued on the bus
+             */
+            #[inline]
+            pub fn set_read_style(&mut self, read_style: ReadStyle) {
+                self.read_style = read_style;
+            }
+
+            /**
+             * Function used to get the health of the port
+             */
+            #[inline]
+            pub fn port_status(&self) -> PortStatus {
+                match self.failures {
+                    0 => PortStatus::Healthy,
+                    f if f < PORT_FAILURE_THRESHOLD => PortStatus::Degraded,
+                    _ => PortStatus::Failed,
+                }
+            }
+
+            /**
+             * Function used to mark the port as failed, further accesses return PortUnavailable
+             */
+            #[inline]
+            pub fn mark_failed(&mut self) {
+                self.failures = PORT_FAILURE_THRESHOLD;
+            }
+
+            /**
+             * Function used to clear the failure accounting of the port
+             */
+            #[inline]
+            pub fn reset_port_status(&mut self) {
+                self.failures = 0;
+            }
+
+            /**
+             * Private function used to account the result of a port access
+             */
+            #[inline]
+            fn record_access<T>(&mut self, result: Result<T, Error<E>>) -> Result<T, Error<E>> {
+                match result {
+                    Ok(_) => self.failures = 0,
+                    Err(_) => self.failures = self.failures.saturating_add(1),
+                }
+                result
+            }
         }
     };
 }
@@ -60,7 +117,7 @@ macro_rules! read_write {
                                     sync(cfg(not(feature = "async")), self = $port_literal,),
                                     async(feature = "async", keep_self)
                                 )]
-        impl<I2C, E, State> Regread for $port_name<I2C, State>
+        impl<I2C, E, State> Regread<E> for $port_name<I2C, State>
         where
             I2C: I2c<Error = E>,
         {
@@ -68,14 +125,28 @@ macro_rules! read_write {
              * Private function used to read the chip registers using i2c
              */
             #[inline]
-            async fn read_config(&mut self, register: Register) -> Result<u8, Error> {
+            async fn read_config(&mut self, register: Register) -> Result<u8, Error<E>> {
+                if self.port_status() == PortStatus::Failed {
+                    return Err(Error::Protocol(ProtocolError::PortUnavailable));
+                }
                 let register_address = register as u8 | self.port as u8;
 
                 let mut rx_buffer: [u8; 1] = [0; 1];
-                self.i2c
-                    .write_read(self.address, &[register_address], &mut rx_buffer)
-                    .await
-                    .map_err(i2c_comm_error)?;
+                let result = match self.read_style {
+                    ReadStyle::RepeatedStart => {
+                        self.i2c
+                            .write_read(self.address, &[register_address], &mut rx_buffer)
+                            .await
+                    }
+                    ReadStyle::StopThenRead => {
+                        match self.i2c.write(self.address, &[register_address]).await {
+                            Ok(()) => self.i2c.read(self.address, &mut rx_buffer).await,
+                            Err(e) => Err(e),
+                        }
+                    }
+                }
+                .map_err(i2c_comm_error);
+                self.record_access(result)?;
                 Ok(rx_buffer[0])
             }
 
@@ -83,14 +154,44 @@ macro_rules! read_write {
              * Private function used to write the chip registers using i2c
              */
             #[inline]
-            async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error> {
+            async fn write_config(&mut self, register: Register, value: u8) -> Result<(), Error<E>> {
+                if self.port_status() == PortStatus::Failed {
+                    return Err(Error::Protocol(ProtocolError::PortUnavailable));
+                }
                 let register_address = register as u8 | self.port as u8;
+                #[cfg(feature = "strict")]
+                check_reserved_bits(register, value)?;
 
-                self.i2c
+                let result = self
+                    .i2c
                     .write(self.address, &[register_address, value])
                     .await
-                    .map_err(i2c_comm_error)?;
-                Ok(())
+                    .map_err(i2c_comm_error);
+                self.record_access(result)
+            }
+        }
+
+        #[maybe_async_cfg::maybe(
+                                    sync(cfg(not(feature = "async")), self = $port_literal,),
+                                    async(feature = "async", keep_self)
+                                )]
+        impl<I2C, E, State> $port_name<I2C, State>
+        where
+            I2C: I2c<Error = E>,
+        {
+            /**
+             * Function used to render the current pin states of the port, GPIO is read once per call
+             */
+            #[inline]
+            pub async fn format_port<W: core::fmt::Write>(
+                &mut self,
+                w: &mut W,
+                style: FormatStyle,
+            ) -> Result<(), Error<E>> {
+                let gpio = self.read_config(Register::Gpio).await?;
+
+                format_byte(gpio, self.port, style, w)
+                    .map_err(|_| Error::Protocol(ProtocolError::FormatFailed))
             }
         }
     };
@@ -107,20 +208,47 @@ macro_rules! set_as {
         where
             I2C: I2c<Error = E>,
         {
+            /**
+             * Function used to create a new handler once the device answers, the probe read
+             * is retried every millisecond until timeout_ms expires
+             */
+            #[inline]
+            pub async fn new_wait_ready<D: DelayNs>(
+                i2c: I2C,
+                address: u8,
+                delay: &mut D,
+                timeout_ms: u32,
+            ) -> Result<Self, Error<E>> {
+                let mut handler = Self::new(i2c, address);
+                let mut elapsed_ms = 0;
+
+                loop {
+                    match handler.read_config(Register::Iocon).await {
+                        Ok(_) => return Ok(handler),
+                        Err(e) if elapsed_ms >= timeout_ms => return Err(e),
+                        Err(_) => handler.reset_port_status(),
+                    }
+                    delay.delay_ms(1).await;
+                    elapsed_ms += 1;
+                }
+            }
+
             /**
              * Function used to set the chip/port/pin as input
              */
             #[inline]
             pub async fn set_as_input(
                 mut self,
-            ) -> Result<$port_name<I2C, InputConfiguring>, Error> {
+            ) -> Result<$port_name<I2C, InputConfiguring>, Error<E>> {
                 self.write_config(Register::Iodir, 0xFF)
                     .await?;
 
                 Ok($port_name {
                     i2c: self.i2c,
                     address: self.address,
+                    read_style: self.read_style,
                     port: self.port,
+                    failures: self.failures,
                     state: core::marker::PhantomData::<InputConfiguring>,
                 })
             }
@@ -129,14 +257,16 @@ macro_rules! set_as {
              * Function used to set the chip/port/pin as output
              */
             #[inline]
-            pub async fn set_as_output(mut self) -> Result<$port_name<I2C, OutputReady>, Error> {
+            pub async fn set_as_output(mut self) -> Result<$port_name<I2C, OutputReady>, Error<E>> {
                 self.write_config(Register::Iodir, 0x00)
                     .await?;
 
                 Ok($port_name {
                     i2c: self.i2c,
                     address: self.address,
+                    read_style: self.read_style,
                     port: self.port,
+                    failures: self.failures,
                     state: core::marker::PhantomData::<OutputReady>,
                 })
             }
@@ -158,7 +288,7 @@ macro_rules! outputready {
              * Function used to write the output value to be set on chip/port/pin
              */
             #[inline]
-            pub async fn write(&mut self, value: u8) -> Result<(), Error> {
+            pub async fn write(&mut self, value: u8) -> Result<(), Error<E>> {
                 let register_address = Register::Gpio as u8 | self.port as u8;
                 self.write_config(Register::Gpio, value).await?;
 
@@ -169,7 +299,7 @@ macro_rules! outputready {
              * Function used to write the output value to be set on pin
              */
             #[inline]
-            pub async fn write_pin(&mut self, pin: PinNumber, value: PinSet) -> Result<(), Error> {
+            pub async fn write_pin(&mut self, pin: PinNumber, value: PinSet) -> Result<(), Error<E>> {
                 let mut result = self.read_config(Register::Gpio).await?;
 
                 result = match value {
@@ -177,10 +307,115 @@ macro_rules! outputready {
                     PinSet::Low => bit_clear(result, pin),
                 };
 
-                self.write_config(Register::Gpio, result).await.map_err(i2c_comm_error)?;
+                self.write_config(Register::Gpio, result).await?;
 
                 Ok(())
             }
+
+            /**
+             * Function used to write the output value straight to the output latch (OLAT)
+             */
+            #[inline]
+            pub async fn write_output_latch(&mut self, value: u8) -> Result<(), Error<E>> {
+                self.write_config(Register::Olat, value).await
+            }
+
+            /**
+             * Function used to read the output latch (OLAT), unlike GPIO it is not affected by
+             * external loading of the pins
+             */
+            #[inline]
+            pub async fn read_output_latch(&mut self) -> Result<u8, Error<E>> {
+                self.read_config(Register::Olat).await
+            }
+
+            /**
+             * Function used to write the output value of a pin with a read-modify-write of the
+             * output latch (OLAT), so externally pulled pins are not copied into the latch
+             */
+            #[inline]
+            pub async fn write_pin_latch(
+                &mut self,
+                pin: PinNumber,
+                value: PinSet,
+            ) -> Result<(), Error<E>> {
+                let result = self.read_config(Register::Olat).await?;
+
+                let result = match value {
+                    PinSet::High => bit_set(result, pin),
+                    PinSet::Low => bit_clear(result, pin),
+                };
+
+                self.write_config(Register::Olat, result).await
+            }
+
+            /**
+             * Function used to drive the pins of set_mask high and the pins of clear_mask low
+             * with a single latch read and write, pins in both masks are set
+             */
+            #[inline]
+            pub async fn modify_pins(&mut self, set_mask: u8, clear_mask: u8) -> Result<(), Error<E>> {
+                let result = self.read_config(Register::Olat).await?;
+
+                self.write_config(Register::Olat, (result & !clear_mask) | set_mask)
+                    .await
+            }
+
+            /**
+             * Function used to invert the pins of mask with a single latch read and write
+             */
+            #[inline]
+            pub async fn toggle_pins(&mut self, mask: u8) -> Result<(), Error<E>> {
+                let result = self.read_config(Register::Olat).await?;
+
+                self.write_config(Register::Olat, result ^ mask).await
+            }
+
+            /**
+             * Function used to invert the output value of a pin
+             */
+            #[inline]
+            pub async fn toggle_pin(&mut self, pin: PinNumber) -> Result<(), Error<E>> {
+                self.toggle_pins(pin_number_to_mask(pin) as u8).await
+            }
+
+            /**
+             * Function used to verify an output pin by comparing its latch (OLAT) with the pin level (GPIO)
+             */
+            #[inline]
+            pub async fn verify_pin(&mut self, pin: PinNumber) -> Result<PinVerify, Error<E>> {
+                let iodir = self.read_config(Register::Iodir).await?;
+                let olat = self.read_config(Register::Olat).await?;
+                let gpio = self.read_config(Register::Gpio).await?;
+
+                Ok(classify_output(iodir, olat, gpio, pin))
+            }
+
+            /**
+             * Function used to release the port lines and turn the whole port into input
+             * (half-duplex bus turnaround), waiting settle_us before the port is handed back
+             */
+            #[inline]
+            pub async fn turnaround_to_input<D: DelayNs>(
+                mut self,
+                delay: &mut D,
+                settle_us: u32,
+            ) -> Result<$port_name<I2C, InputReady>, Error<E>> {
+                self.write_config(Register::Iodir, 0xFF).await?;
+
+                if settle_us > 0 {
+                    delay.delay_us(settle_us).await;
+                }
+
+                Ok($port_name {
+                    i2c: self.i2c,
+                    address: self.address,
+                    read_style: self.read_style,
+                    port: self.port,
+                    failures: self.failures,
+                    state: core::marker::PhantomData::<InputReady>,
+                })
+            }
         }
     };
 }
@@ -199,9 +434,9 @@ macro_rules! inputready {
              * Function used to read the input
              */
             #[inline]
-            pub async fn read(&mut self) -> Result<u8, Error> {
+            pub async fn read(&mut self) -> Result<u8, Error<E>> {
 
-                let mut result = self.read_config(Register::Gpio).await.map_err(i2c_comm_error)?;
+                let mut result = self.read_config(Register::Gpio).await?;
 
                 Ok(result)
             }
@@ -210,16 +445,25 @@ macro_rules! inputready {
              * Function used to read the input pin
              */
             #[inline]
-            pub async fn read_pin(&mut self, pin: PinNumber) -> Result<u8, Error> {
+            pub async fn read_pin(&mut self, pin: PinNumber) -> Result<u8, Error<E>> {
                 let result = self.read().await?;
                 Ok(bit_read(result, pin))
             }
 
+            /**
+             * Function used to read the input pin as a pin level
+             */
+            #[inline]
+            pub async fn read_pin_level(&mut self, pin: PinNumber) -> Result<PinSet, Error<E>> {
+                let result = self.read().await?;
+                Ok(bit_level(result, pin))
+            }
+
             /**
              * Function used to disable the interrupt on the input
              */
             #[inline]
-            pub async fn disable_interrupt(&mut self, pin: PinNumber) -> Result<(), Error> {
+            pub async fn disable_interrupt(&mut self, pin: PinNumber) -> Result<(), Error<E>> {
                 let mut reg = self.read_config(Register::Gpinten).await?;
 
                 reg = bit_clear(reg, pin);
@@ -234,7 +478,7 @@ macro_rules! inputready {
             pub async fn enable_interrupt(
                 &mut self,
                 pin: PinNumber,
-            ) -> Result<(), Error> {
+            ) -> Result<(), Error<E>> {
                 let mut reg = self.read_config(Register::Gpinten).await?;
 
                 reg = bit_set(reg, pin);
@@ -242,13 +486,89 @@ macro_rules! inputready {
             }
 
             /**
-             * Function used to verify the interrupt on the input
+             * Function used to read the pin levels latched (INTCAP) when the last interrupt fired,
+             * reading the capture clears the interrupt
+             */
+            #[inline]
+            pub async fn read_interrupt_capture(&mut self) -> Result<u8, Error<E>> {
+                self.read_config(Register::Intcap).await
+            }
+
+            /**
+             * Function used to get the pin that fired the interrupt together with its level at
+             * interrupt time, the lowest pin is returned when several are flagged (see
+             * get_interrupted_pins), returns None if no pin is flagged
+             */
+            #[inline]
+            pub async fn get_interrupted_pin_level(
+                &mut self,
+            ) -> Result<Option<(PinNumber, PinSet)>, Error<E>> {
+                let intf = self.read_config(Register::Intf).await?;
+                let Some(pin) = PinFlags(intf).next() else {
+                    return Ok(None);
+                };
+                let intcap = self.read_interrupt_capture().await?;
+
+                Ok(Some((pin, bit_level(intcap, pin))))
+            }
+
+            /**
+             * Function used to get every pin of the port flagged in INTF
+             */
+            #[inline]
+            pub async fn get_interrupted_pins(&mut self) -> Result<PinFlags, Error<E>> {
+                Ok(PinFlags(self.read_config(Register::Intf).await?))
+            }
+
+            /**
+             * Function used to verify the interrupt on the input, communication errors are
+             * returned instead of being reported as no interrupt
+             */
+            #[inline]
+            pub async fn try_get_interrupted_pin(
+                &mut self,
+            ) -> Result<Option<PinNumber>, Error<E>> {
+                let pin_msk = self.read_config(Register::Intf).await?;
+
+                Ok(pin_mask_to_number(PinMask::from(pin_msk)))
+            }
+
+            /**
+             * Function used to verify the interrupt on the input, a communication error is
+             * reported as None (see try_get_interrupted_pin)
              */
             #[inline]
             pub async fn get_interrupted_pin(&mut self) -> Option<PinNumber> {
-                let pin_msk = self.read_config(Register::Intf).await.unwrap_or(0);
+                self.try_get_interrupted_pin().await.unwrap_or(None)
+            }
+
+            /**
+             * Function used to turn the whole port into output (half-duplex bus turnaround),
+             * the latch (OLAT) is preloaded with value before the drivers are enabled so the
+             * lines never drive a stale level, settle_us is waited before the port is handed back
+             */
+            #[inline]
+            pub async fn turnaround_to_output<D: DelayNs>(
+                mut self,
+                value: u8,
+                delay: &mut D,
+                settle_us: u32,
+            ) -> Result<$port_name<I2C, OutputReady>, Error<E>> {
+                self.write_config(Register::Olat, value).await?;
+                self.write_config(Register::Iodir, 0x00).await?;
+
+                if settle_us > 0 {
+                    delay.delay_us(settle_us).await;
+                }
 
-                pin_mask_to_number(PinMask::from(pin_msk))
+                Ok($port_name {
+                    i2c: self.i2c,
+                    address: self.address,
+                    read_style: self.read_style,
+                    port: self.port,
+                    failures: self.failures,
+                    state: core::marker::PhantomData::<OutputReady>,
+                })
             }
         }
     };
@@ -268,7 +588,7 @@ macro_rules! inputconfiguring {
              * Function used to set the pull on the input
              */
             #[inline]
-            pub async fn set_pull(mut self, pull: PinSet) -> Result<Self, Error> {
+            pub async fn set_pull(mut self, pull: PinSet) -> Result<Self, Error<E>> {
                 let result = match pull {
                     PinSet::High => 0xFF,
                     PinSet::Low => 0x00,
@@ -286,7 +606,7 @@ macro_rules! inputconfiguring {
             pub async fn set_interrupt_mirror(
                 mut self,
                 mirror: InterruptMirror,
-            ) -> Result<Self, Error> {
+            ) -> Result<Self, Error<E>> {
                 let mut reg = self.read_config(Register::Iocon).await?;
 
                 match mirror {
@@ -304,6 +624,43 @@ macro_rules! inputconfiguring {
                 Ok(self)
             }
 
+            /**
+             * Function used to set the output type of the INT pin (open-drain or push-pull
+             * with its active level)
+             */
+            #[inline]
+            pub async fn set_interrupt_output(
+                mut self,
+                output: InterruptOutput,
+            ) -> Result<Self, Error<E>> {
+                let reg = self.read_config(Register::Iocon).await?;
+
+                self.write_config(Register::Iocon, iocon_interrupt_output(reg, output))
+                    .await?;
+
+                Ok(self)
+            }
+
+            /**
+             * Function used to set the polarity (IPOL) of an input pin
+             */
+            #[inline]
+            pub async fn set_polarity(
+                mut self,
+                pin: PinNumber,
+                polarity: Polarity,
+            ) -> Result<Self, Error<E>> {
+                let mut reg = self.read_config(Register::Ipol).await?;
+
+                reg = match polarity {
+                    Polarity::Normal => bit_clear(reg, pin),
+                    Polarity::Inverted => bit_set(reg, pin),
+                };
+
+                self.write_config(Register::Ipol, reg).await?;
+                Ok(self)
+            }
+
             /**
              * Function used to choose the pin as interrupt on the input
              */
@@ -312,7 +669,7 @@ macro_rules! inputconfiguring {
                 mut self,
                 pin: PinNumber,
                 interrupt_on: InterruptOn,
-            ) -> Result<Self, Error> {
+            ) -> Result<Self, Error<E>> {
                 let mut reg = self.read_config(Register::Intcon).await?;
 
                 reg = match interrupt_on {
@@ -332,11 +689,11 @@ macro_rules! inputconfiguring {
                 mut self,
                 pin: PinNumber,
                 value: PinSet,
-            ) -> Result<Self, Error> {
+            ) -> Result<Self, Error<E>> {
                 let intcon = self.read_config(Register::Intcon).await?;
 
                 if bit_read(intcon, pin) != 1 {
-                    return Err(Error::InvalidInterruptSetting);
+                    return Err(Error::Protocol(ProtocolError::InvalidInterruptSetting));
                 }
 
                 let mut reg = self.read_config(Register::Defval).await?; //change only valid if intcon is set to 1
@@ -358,7 +715,9 @@ macro_rules! inputconfiguring {
                 $port_name {
                     i2c: self.i2c,
                     address: self.address,
+                    read_style: self.read_style,
                     port: self.port,
+                    failures: self.failures,
                     state: core::marker::PhantomData::<InputReady>,
                 }
             }
@@ -446,4 +805,575 @@ mod tests {
         //finalize execution
         i2c.done();
     }
+
+    #[test]
+    fn test_port_status_failed() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+                vector1(0xff),
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+                vector1(0xff),
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Gpio as u8 | MyPort::Portb as u8, 0xff),
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::Other),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myportb: PortB<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            PortB::new(i2c.clone(), 0x40);
+
+        assert_eq!(PortStatus::Healthy, myportb.port_status());
+        let result = myportb.read_config(Register::Gpio);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+        assert_eq!(PortStatus::Degraded, myportb.port_status());
+        let result = myportb.read_config(Register::Gpio);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+        let result = myportb.write_config(Register::Gpio, 0xff);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+        assert_eq!(PortStatus::Failed, myportb.port_status());
+
+        //no transaction is issued once the port is failed
+        let result = myportb.read_config(Register::Gpio);
+        assert_eq!(
+            Error::Protocol(ProtocolError::PortUnavailable),
+            result.unwrap_err()
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_port_status_recovered() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+                vector1(0xff),
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::Other),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+                vector1(0xff),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+                vector1(0xff),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myporta: PortA<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            PortA::new(i2c.clone(), 0x40);
+
+        let result = myporta.read_config(Register::Gpio);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+        let result = myporta.read_config(Register::Gpio);
+        assert_eq!(0xff, result.unwrap());
+        assert_eq!(PortStatus::Healthy, myporta.port_status());
+
+        myporta.mark_failed();
+        assert_eq!(
+            Error::Protocol(ProtocolError::PortUnavailable),
+            myporta.read_config(Register::Gpio).unwrap_err()
+        );
+        myporta.reset_port_status();
+        assert_eq!(0xff, myporta.read_config(Register::Gpio).unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_new_wait_ready_porta() {
+        let mut expectations = Vec::new();
+        for _ in 0..4 {
+            expectations.push(
+                I2cTransaction::write_read(
+                    0x40,
+                    vector1(Register::Iocon as u8 | MyPort::Porta as u8),
+                    vector1(0x00),
+                )
+                .with_error(embedded_hal::i2c::ErrorKind::Other),
+            );
+        }
+        expectations.push(I2cTransaction::write_read(
+            0x40,
+            vector1(Register::Iocon as u8 | MyPort::Porta as u8),
+            vector1(0x00),
+        ));
+        let mut i2c = I2cMock::new(&expectations);
+
+        let myporta: PortA<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            PortA::new_wait_ready(
+                i2c.clone(),
+                0x40,
+                &mut embedded_hal_mock::eh1::delay::NoopDelay,
+                10,
+            )
+            .unwrap();
+
+        assert_eq!(PortStatus::Healthy, myporta.port_status());
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_verify_pin_portb() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iodir as u8 | MyPort::Portb as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+                vector1(0x10),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myportb = PortB {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            failures: 0,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        let result = myportb.verify_pin(PinNumber::Pin4).unwrap();
+        assert_eq!(PinVerify::ShortToVcc, result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_config_stop_then_read_porta() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector1(Register::Gpio as u8 | MyPort::Porta as u8)),
+            I2cTransaction::read(0x40, vector1(0xa5)),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myporta: PortA<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            PortA::new(i2c.clone(), 0x40);
+        myporta.set_read_style(ReadStyle::StopThenRead);
+        let result = myporta.read_config(Register::Gpio);
+        assert_eq!(0xa5, result.unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_turnaround_porta() {
+        let expectations = [
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Porta as u8, 0xff),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+                vector1(0x3c),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Porta as u8, 0x81),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Porta as u8, 0x00),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let myporta = PortA {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            failures: 0,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+        let mut delay = embedded_hal_mock::eh1::delay::NoopDelay;
+
+        let mut myporta = myporta.turnaround_to_input(&mut delay, 10).unwrap();
+        assert_eq!(0x3c, myporta.read().unwrap());
+        let myporta = myporta.turnaround_to_output(0x81, &mut delay, 0).unwrap();
+        assert_eq!(PortStatus::Healthy, myporta.port_status());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_turnaround_error_portb() {
+        let expectations = [I2cTransaction::write(
+            0x40,
+            vector2(Register::Olat as u8 | MyPort::Portb as u8, 0x00),
+        )
+        .with_error(ErrorKind::Other)];
+        let mut i2c = I2cMock::new(&expectations);
+        let myportb = PortB {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            failures: 0,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        let result =
+            myportb.turnaround_to_output(0x00, &mut embedded_hal_mock::eh1::delay::NoopDelay, 5);
+        assert_eq!(Error::Transport(ErrorKind::Other), result.unwrap_err());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_pin_level_portb() {
+        let expectations = [I2cTransaction::write_read(
+            0x40,
+            vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+            vector1(0x04),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myportb = PortB {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            failures: 0,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        let result = myportb.read_pin_level(PinNumber::Pin2).unwrap();
+        assert_eq!(PinSet::High, result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_format_port_porta() {
+        let expectations = [I2cTransaction::write_read(
+            0x40,
+            vector1(Register::Gpio as u8 | MyPort::Porta as u8),
+            vector1(0x03),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myporta: PortA<embedded_hal_mock::common::Generic<I2cTransaction>, Configuring> =
+            PortA::new(i2c.clone(), 0x40);
+        let mut text = std::string::String::new();
+
+        myporta.format_port(&mut text, FormatStyle::Named).unwrap();
+        assert_eq!(
+            "GPA0=1 GPA1=1 GPA2=0 GPA3=0 GPA4=0 GPA5=0 GPA6=0 GPA7=0",
+            text
+        );
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_shared_bus_refcell_device() {
+        use core::cell::RefCell;
+        use embedded_hal_bus::i2c::RefCellDevice;
+
+        let expectations = [
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Porta as u8, 0x00),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iodir as u8 | MyPort::Portb as u8, 0xff),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Gpio as u8 | MyPort::Porta as u8, 0x5a),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Gpio as u8 | MyPort::Portb as u8),
+                vector1(0xa5),
+            ),
+        ];
+        let i2c_ref_cell = RefCell::new(I2cMock::new(&expectations));
+
+        {
+            let mut myporta = PortA::new(RefCellDevice::new(&i2c_ref_cell), 0x40)
+                .set_as_output()
+                .unwrap();
+            let mut myportb = PortB::new(RefCellDevice::new(&i2c_ref_cell), 0x40)
+                .set_as_input()
+                .unwrap()
+                .ready();
+
+            myporta.write(0x5a).unwrap();
+            assert_eq!(0xa5, myportb.read().unwrap());
+        }
+
+        //finalize execution
+        i2c_ref_cell.into_inner().done();
+    }
+
+    #[test]
+    fn test_set_polarity_portb() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Ipol as u8 | MyPort::Portb as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Ipol as u8 | MyPort::Portb as u8, 0x10),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let myportb = PortB {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            failures: 0,
+            state: core::marker::PhantomData::<InputConfiguring>,
+        };
+
+        myportb
+            .set_polarity(PinNumber::Pin4, Polarity::Inverted)
+            .unwrap();
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_get_interrupted_pin_level_porta() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intf as u8 | MyPort::Porta as u8),
+                vector1(0x08),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intcap as u8 | MyPort::Porta as u8),
+                vector1(0x00),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intcap as u8 | MyPort::Porta as u8),
+                vector1(0x41),
+            ),
+            //two pins flagged
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intf as u8 | MyPort::Porta as u8),
+                vector1(0x48),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Intcap as u8 | MyPort::Porta as u8),
+                vector1(0x08),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myporta = PortA {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            failures: 0,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        let result = myporta.get_interrupted_pin_level().unwrap();
+        assert_eq!(Some((PinNumber::Pin3, PinSet::Low)), result);
+        assert_eq!(0x41, myporta.read_interrupt_capture().unwrap());
+        let result = myporta.get_interrupted_pin_level().unwrap();
+        assert_eq!(Some((PinNumber::Pin3, PinSet::High)), result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_interrupt_output_porta() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Iocon as u8 | MyPort::Porta as u8),
+                vector1(0x04),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Iocon as u8 | MyPort::Porta as u8, 0x02),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let myporta = PortA {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            failures: 0,
+            state: core::marker::PhantomData::<InputConfiguring>,
+        };
+
+        myporta
+            .set_interrupt_output(InterruptOutput::ActiveHigh)
+            .unwrap();
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_get_interrupted_pins_portb() {
+        let expectations = [I2cTransaction::write_read(
+            0x40,
+            vector1(Register::Intf as u8 | MyPort::Portb as u8),
+            vector1(0x06),
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myportb = PortB {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            failures: 0,
+            state: core::marker::PhantomData::<InputReady>,
+        };
+
+        let result = myportb.get_interrupted_pins().unwrap();
+        assert_eq!(PinFlags(0x06), result);
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_pin_latch_porta() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Porta as u8),
+                vector1(0x01),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Porta as u8, 0x00),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Porta as u8),
+                vector1(0x00),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myporta = PortA {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            failures: 0,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        myporta
+            .write_pin_latch(PinNumber::Pin0, PinSet::Low)
+            .unwrap();
+        assert_eq!(0x00, myporta.read_output_latch().unwrap());
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_modify_toggle_pins_portb() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x0f),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Portb as u8, 0x1c),
+            ),
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Portb as u8),
+                vector1(0x1c),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Portb as u8, 0x1d),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myportb = PortB {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Portb,
+            failures: 0,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        myportb.modify_pins(0x10, 0x03).unwrap();
+        myportb.toggle_pins(0x01).unwrap();
+
+        //finalize execution
+        i2c.done();
+    }
+
+    #[test]
+    fn test_toggle_pin_porta() {
+        let expectations = [
+            I2cTransaction::write_read(
+                0x40,
+                vector1(Register::Olat as u8 | MyPort::Porta as u8),
+                vector1(0x80),
+            ),
+            I2cTransaction::write(
+                0x40,
+                vector2(Register::Olat as u8 | MyPort::Porta as u8, 0x00),
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut myporta = PortA {
+            i2c: i2c.clone(),
+            address: 0x40,
+            read_style: ReadStyle::RepeatedStart,
+            port: MyPort::Porta,
+            failures: 0,
+            state: core::marker::PhantomData::<OutputReady>,
+        };
+
+        myporta.toggle_pin(PinNumber::Pin7).unwrap();
+
+        //finalize execution
+        i2c.done();
+    }
 }
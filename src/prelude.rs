@@ -1,11 +1,17 @@
 #![allow(dead_code, unused)]
 
+pub use crate::codec::{
+    DefvalPair, GpintenPair, GpioPair, GppuPair, IntcapPair, IntconPair, IodirPair, IpolPair,
+    OlatPair,
+};
 use crate::registers::*;
 use core::fmt::Display;
 
 const DEFAULT_ADDRESS: u8 = 0x20;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PinNumber {
     Pin0,
     Pin1,
@@ -17,29 +23,200 @@ pub enum PinNumber {
     Pin7,
 }
 
+impl PinNumber {
+    /// All pin numbers, Pin0 first
+    pub const ALL: [PinNumber; 8] = PIN_NUMBERS;
+
+    /**
+     * Function used to iterate over all pin numbers, Pin0 first
+     */
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = PinNumber> {
+        Self::ALL.into_iter()
+    }
+}
+
+/// Datasheet (silkscreen) pin names, convertible from and into a (MyPort, PinNumber) pair
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GpioName {
+    GPA0,
+    GPA1,
+    GPA2,
+    GPA3,
+    GPA4,
+    GPA5,
+    GPA6,
+    GPA7,
+    GPB0,
+    GPB1,
+    GPB2,
+    GPB3,
+    GPB4,
+    GPB5,
+    GPB6,
+    GPB7,
+}
+
+impl GpioName {
+    /// All pin names, GPA0 first
+    pub const ALL: [GpioName; 16] = GPIO_NAMES;
+
+    /**
+     * Function used to get the port of the pin
+     */
+    #[inline]
+    pub fn port(self) -> MyPort {
+        if (self as u8) < 8 {
+            MyPort::Porta
+        } else {
+            MyPort::Portb
+        }
+    }
+
+    /**
+     * Function used to get the number of the pin within its port
+     */
+    #[inline]
+    pub fn pin(self) -> PinNumber {
+        PIN_NUMBERS[self as usize % 8]
+    }
+}
+
+impl From<GpioName> for (MyPort, PinNumber) {
+    fn from(value: GpioName) -> Self {
+        (value.port(), value.pin())
+    }
+}
+
+impl From<(MyPort, PinNumber)> for GpioName {
+    fn from((port, pin): (MyPort, PinNumber)) -> Self {
+        GPIO_NAMES[port as usize * 8 + pin as usize]
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MyPort {
     Porta = 0x00,
     Portb = 0x01,
 }
 
+impl MyPort {
+    /// Both ports, Port A first
+    pub const ALL: [MyPort; 2] = [MyPort::Porta, MyPort::Portb];
+
+    /**
+     * Function used to iterate over both ports, Port A first
+     */
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = MyPort> {
+        Self::ALL.into_iter()
+    }
+}
+
 /// Enum used for mcp23017 addressing based on pin connection
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SlaveAddressing {
     Low,
     High,
 }
 
+/// Input polarity (IPOL), an inverted input reads back the opposite of its pin level
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Polarity {
+    Normal,
+    Inverted,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PinSet {
     Low = 0,
     High = 1,
 }
 
-///Valid error codes
+/// Set of pins of a port (one bit per pin), iterating yields the pins from Pin0 up
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PinFlags(pub u8);
+
+impl PinFlags {
+    /**
+     * Function used to verify if the pin is in the set
+     */
+    #[inline]
+    pub fn contains(&self, pin: PinNumber) -> bool {
+        self.0 & (1 << pin as u8) != 0
+    }
+
+    /**
+     * Function used to verify if the set has no pins
+     */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /**
+     * Function used to get the number of pins in the set
+     */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+}
+
+impl Iterator for PinFlags {
+    type Item = PinNumber;
+
+    fn next(&mut self) -> Option<PinNumber> {
+        if self.0 == 0 {
+            return None;
+        }
+        let pin = PIN_NUMBERS[self.0.trailing_zeros() as usize];
+        self.0 &= self.0 - 1;
+        Some(pin)
+    }
+}
+
+///Valid error codes, split between bus (transport) and driver (protocol) failures
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Error {
-    CommunicationErr,
+#[non_exhaustive]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    Transport(E),
+    Protocol(ProtocolError),
+}
+
+impl<E: embedded_hal::i2c::Error> Error<E> {
+    /**
+     * Function used to get the kind of the bus error (NACK, arbitration loss, ...), returns
+     * None for protocol errors
+     */
+    #[inline]
+    pub fn i2c_kind(&self) -> Option<embedded_hal::i2c::ErrorKind> {
+        match self {
+            Error::Transport(e) => Some(e.kind()),
+            Error::Protocol(_) => None,
+        }
+    }
+}
+
+///Valid protocol error codes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProtocolError {
     InvalidParameter,
     InvalidDie,
     InvalidManufacturer,
@@ -47,14 +224,147 @@ pub enum Error {
     MissingI2C,
     PinIsNotInput,
     InvalidInterruptSetting,
+    PortUnavailable,
+    WriteVerifyFailed,
+    FormatFailed,
+    ReservedBits,
+    UnknownAddressing,
 }
 
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InterruptOn {
     PinChange = 0,
     ChangeFromRegister = 1,
 }
 
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InterruptMirror {
     MirrorOn = 0b01000000,
     MirrorOff = 0b10111111,
 }
+
+/// Address pointer behaviour between transferred bytes (IOCON.SEQOP)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AddressMode {
+    /// Address pointer increments through the register file (power-on default)
+    #[default]
+    Sequential,
+    /// Address pointer toggles between the A/B registers of a pair
+    Byte,
+}
+
+/// Output type of the INT pins (IOCON.ODR and IOCON.INTPOL)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptOutput {
+    /// Push-pull, driven low on interrupt (power-on default)
+    #[default]
+    ActiveLow,
+    /// Push-pull, driven high on interrupt
+    ActiveHigh,
+    /// Open-drain, pulled low on interrupt, needs an external pull-up
+    OpenDrain,
+}
+
+/// Health of a port as accounted by the driver
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PortStatus {
+    Healthy,
+    Degraded,
+    Failed,
+}
+
+/// Result of comparing an output pin latch (OLAT) with its actual level (GPIO). A single
+/// readback cannot tell a short from another driver overriding the pin, so an external
+/// override is reported as ShortToGround or ShortToVcc depending on the level it forces
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PinVerify {
+    /// The pin level matches the latch
+    Driving,
+    /// The latch is high but the pin reads low (short or external override to ground)
+    ShortToGround,
+    /// The latch is low but the pin reads high (short or external override to Vcc)
+    ShortToVcc,
+    /// The pin is configured as input (IODIR bit set), nothing to verify
+    NotAnOutput,
+}
+
+/// How register reads are issued on the bus
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadStyle {
+    /// Register address write and data read joined by a repeated start (write_read)
+    #[default]
+    RepeatedStart,
+    /// Register address write terminated by a stop, followed by a separate read
+    StopThenRead,
+}
+
+/// Input configuration presets for common attachments, applied with apply_preset
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Preset {
+    /// Button to ground: pull-up, interrupt on every change
+    PushButtonActiveLow,
+    /// Normally open switch to ground: pull-up, interrupt while closed (compare against high)
+    LimitSwitchNO,
+    /// Quadrature encoder channel: pull-up, interrupt on every change
+    EncoderChannel,
+}
+
+/// How format_port renders the pin states of a port
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FormatStyle {
+    /// Pin 7 first, e.g. 0b10000001
+    Binary,
+    /// e.g. 0x81
+    Hex,
+    /// Pin 0 first with the datasheet pin names, e.g. GPA0=1 GPA1=0 ...
+    Named,
+}
+
+/// Whether output writes are read back from the latch (OLAT) and rewritten on mismatch
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PostWriteVerify {
+    #[default]
+    Off,
+    /// Maximum number of rewrites before giving up with WriteVerifyFailed
+    Retries(u8),
+}
+
+/// Probable cause of an interrupt asserted with no flag set in INTF
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SpuriousInterrupt {
+    ClearedByRaceRead,
+    NoiseOnIntLine,
+    MirrorMisconfig,
+}
+
+/// Register addressing layout selected by IOCON.BANK
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Bank {
+    Bank0,
+    Bank1,
+}
+
+/// Probe report of a device found by enumerate_bus
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceInfo {
+    pub address: u8,
+    pub iocon_readable: bool,
+    pub bank: Option<Bank>,
+}
@@ -5,18 +5,23 @@ use core::fmt::Display;
 const DEFAULT_ADDRESS: u8 = 0x20; // Default address
                                   ////// STATES ////////
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Configuring;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct OutputReady;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct InputConfiguring;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct InputReady;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Register {
     Iodir = 0x00,
     Ipol = 0x02,
@@ -31,7 +36,46 @@ pub enum Register {
     Olat = 0x14,
 }
 
+impl Register {
+    /// All registers in bank 0 address order, Iodir first
+    pub const ALL: [Register; 11] = [
+        Register::Iodir,
+        Register::Ipol,
+        Register::Gpinten,
+        Register::Defval,
+        Register::Intcon,
+        Register::Iocon,
+        Register::Gppu,
+        Register::Intf,
+        Register::Intcap,
+        Register::Gpio,
+        Register::Olat,
+    ];
+
+    /**
+     * Function that returns the mask of the bits implemented (writable) in the register,
+     * IOCON bit 0 is unimplemented and INTF/INTCAP are read only
+     */
+    #[inline]
+    pub const fn implemented_mask(self) -> u8 {
+        match self {
+            Register::Iocon => 0xFE,
+            Register::Intf | Register::Intcap => 0x00,
+            _ => 0xFF,
+        }
+    }
+}
+
+pub const IOCON_BANK: u8 = 0x80; // IOCON.BANK bit
+pub const REGISTER_FILE_LEN: usize = 22; // Registers bytes from IODIRA to OLATB
+pub const IOCON_BANK1_PORTA: u8 = 0x05; // IOCON address on port A when BANK = 1
+pub const IOCON_BANK1_PORTB: u8 = 0x15; // IOCON address on port B when BANK = 1
+pub const IOCON_SEQOP: u8 = 0x20; // IOCON.SEQOP bit, sequential addressing disabled
+pub const IOCON_ODR: u8 = 0x04; // IOCON.ODR bit, INT pin open-drain
+pub const IOCON_INTPOL: u8 = 0x02; // IOCON.INTPOL bit, INT pin active-high
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PinMask {
     Pin0 = 0x01,
     Pin1 = 0x02,
@@ -63,24 +107,128 @@ impl From<u8> for PinMask {
     }
 }
 
+/**
+ * Function implements the TryFrom trait into PinNumber enum, values above 7 are rejected
+ */
+impl TryFrom<u8> for PinNumber {
+    type Error = ProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        PIN_NUMBERS
+            .get(value as usize)
+            .copied()
+            .ok_or(ProtocolError::InvalidParameter)
+    }
+}
+
+/**
+ * Function implements the From trait into u8 for PinNumber enum
+ */
+impl From<PinNumber> for u8 {
+    fn from(value: PinNumber) -> Self {
+        value as u8
+    }
+}
+
+/**
+ * Function implements the TryFrom trait into MyPort enum, values above 1 are rejected
+ */
+impl TryFrom<u8> for MyPort {
+    type Error = ProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(MyPort::Porta),
+            0x01 => Ok(MyPort::Portb),
+            _ => Err(ProtocolError::InvalidParameter),
+        }
+    }
+}
+
+/**
+ * Function implements the From trait into u8 for MyPort enum
+ */
+impl From<MyPort> for u8 {
+    fn from(value: MyPort) -> Self {
+        value as u8
+    }
+}
+
+/**
+ * Function implements the TryFrom trait into GpioName enum, values above 15 are rejected
+ */
+impl TryFrom<u8> for GpioName {
+    type Error = ProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        GPIO_NAMES
+            .get(value as usize)
+            .copied()
+            .ok_or(ProtocolError::InvalidParameter)
+    }
+}
+
+/**
+ * Function implements the From trait into u8 for GpioName enum
+ */
+impl From<GpioName> for u8 {
+    fn from(value: GpioName) -> Self {
+        value as u8
+    }
+}
+
 /**
  * Function implements the Display trait into Error enum
  */
-impl Display for Error {
+impl<E: core::fmt::Debug> Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "Communication Error ({:?})", e),
+            Error::Protocol(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/**
+ * Function implements the embedded-hal digital Error trait into Error enum
+ */
+impl<E: core::fmt::Debug> embedded_hal::digital::Error for Error<E> {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+/**
+ * Function implements the Display trait into ProtocolError enum
+ */
+impl Display for ProtocolError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Error::InvalidDie => write!(f, "Invalid Die Number"),
-            Error::CommunicationErr => write!(f, "Not found on address"),
-            Error::InvalidManufacturer => write!(f, "Invalid Manufacturer"),
-            Error::InvalidParameter => write!(f, "Invalid Parameter"),
-            Error::MissingAddress => write!(f, "Missing Device Address"),
-            Error::MissingI2C => write!(f, "Missing I2C Bus"),
-            Error::PinIsNotInput => write!(f, "Pin is not Input"),
-            Error::InvalidInterruptSetting => write!(f, "Invalid Interrupt Setting"),
+            ProtocolError::InvalidDie => write!(f, "Invalid Die Number"),
+            ProtocolError::InvalidManufacturer => write!(f, "Invalid Manufacturer"),
+            ProtocolError::InvalidParameter => write!(f, "Invalid Parameter"),
+            ProtocolError::MissingAddress => write!(f, "Missing Device Address"),
+            ProtocolError::MissingI2C => write!(f, "Missing I2C Bus"),
+            ProtocolError::PinIsNotInput => write!(f, "Pin is not Input"),
+            ProtocolError::InvalidInterruptSetting => write!(f, "Invalid Interrupt Setting"),
+            ProtocolError::PortUnavailable => write!(f, "Port Unavailable"),
+            ProtocolError::WriteVerifyFailed => write!(f, "Write Verify Failed"),
+            ProtocolError::FormatFailed => write!(f, "Format Failed"),
+            ProtocolError::ReservedBits => write!(f, "Reserved Bits Set"),
+            ProtocolError::UnknownAddressing => write!(f, "Unknown Register Addressing"),
         }
     }
 }
 
+/**
+ * Function implements the From trait into Error enum for protocol errors
+ */
+impl<E> From<ProtocolError> for Error<E> {
+    fn from(value: ProtocolError) -> Self {
+        Error::Protocol(value)
+    }
+}
+
 /**
  * Function implements the Display trait into Register enum
  */
@@ -114,6 +262,19 @@ impl Display for MyPort {
     }
 }
 
+/**
+ * Function implements the Display trait into GpioName enum
+ */
+impl Display for GpioName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let port = match self.port() {
+            MyPort::Porta => 'A',
+            MyPort::Portb => 'B',
+        };
+        write!(f, "GP{}{}", port, self.pin() as u8)
+    }
+}
+
 /**
  * Function implements the Display trait into SlaveAddressing enum
  */
@@ -127,10 +288,33 @@ impl Display for SlaveAddressing {
 }
 
 /**
- * Returns communication error
+ * Returns communication error wrapping the bus error
  */
-pub fn i2c_comm_error<E>(_: E) -> Error {
-    Error::CommunicationErr
+pub fn i2c_comm_error<E>(e: E) -> Error<E> {
+    Error::Transport(e)
+}
+
+/**
+ * Function that finds the register layout from IOCON probed at its bank 1 addresses
+ * (0x05/0x15) and at its bank 0 pair (0x0A/0x0B). In bank 0, 0x05/0x15 are GPINTENB and
+ * OLATB, in bank 1, 0x0A is OLATA and 0x0B reads 0, so both layouts can look valid (e.g.
+ * IOCON = 0 with GPINTENB = OLATB = 0x80 against BANK = 1 with OLATA = 0). Returns None
+ * when neither or both layouts match
+ */
+pub fn bank_from_iocon(bank1: [u8; 2], bank0: [u8; 2]) -> Option<Bank> {
+    let unimplemented = !Register::Iocon.implemented_mask();
+    let is_bank1 = bank1[0] == bank1[1]
+        && bank1[0] & IOCON_BANK != 0
+        && bank1[0] & unimplemented == 0
+        && bank0[1] == 0;
+    let is_bank0 =
+        bank0[0] == bank0[1] && bank0[0] & IOCON_BANK == 0 && bank0[0] & unimplemented == 0;
+
+    match (is_bank0, is_bank1) {
+        (true, false) => Some(Bank::Bank0),
+        (false, true) => Some(Bank::Bank1),
+        _ => None,
+    }
 }
 
 /**
@@ -149,6 +333,36 @@ pub fn pin_number_to_mask(pin: PinNumber) -> PinMask {
     }
 }
 
+pub const PIN_NUMBERS: [PinNumber; 8] = [
+    PinNumber::Pin0,
+    PinNumber::Pin1,
+    PinNumber::Pin2,
+    PinNumber::Pin3,
+    PinNumber::Pin4,
+    PinNumber::Pin5,
+    PinNumber::Pin6,
+    PinNumber::Pin7,
+]; // Pin numbers indexed by bit position
+
+pub const GPIO_NAMES: [GpioName; 16] = [
+    GpioName::GPA0,
+    GpioName::GPA1,
+    GpioName::GPA2,
+    GpioName::GPA3,
+    GpioName::GPA4,
+    GpioName::GPA5,
+    GpioName::GPA6,
+    GpioName::GPA7,
+    GpioName::GPB0,
+    GpioName::GPB1,
+    GpioName::GPB2,
+    GpioName::GPB3,
+    GpioName::GPB4,
+    GpioName::GPB5,
+    GpioName::GPB6,
+    GpioName::GPB7,
+]; // Pin names indexed by port * 8 + bit position
+
 /**
  * This function converts a pin mask to a pin number
  */
@@ -190,6 +404,104 @@ pub fn bit_read(byte: u8, pin: PinNumber) -> u8 {
     (byte & (pin_number_to_mask(pin) as u8)) >> (pin as u8)
 }
 
+/**
+ * This function reads a given bit from a byte as a pin level. It must receive the byte and
+ * the pin number to be read
+ */
+pub fn bit_level(byte: u8, pin: PinNumber) -> PinSet {
+    match bit_read(byte, pin) {
+        0 => PinSet::Low,
+        _ => PinSet::High,
+    }
+}
+
+/**
+ * This function renders the pin states of a port byte without allocation. It must receive
+ * the byte, its port, the style and the writer
+ */
+pub fn format_byte<W: core::fmt::Write>(
+    byte: u8,
+    port: MyPort,
+    style: FormatStyle,
+    w: &mut W,
+) -> core::fmt::Result {
+    match style {
+        FormatStyle::Binary => write!(w, "0b{:08b}", byte),
+        FormatStyle::Hex => write!(w, "0x{:02x}", byte),
+        FormatStyle::Named => {
+            let name = match port {
+                MyPort::Porta => "GPA",
+                MyPort::Portb => "GPB",
+            };
+            for pin in 0..8 {
+                if pin > 0 {
+                    w.write_char(' ')?;
+                }
+                write!(w, "{}{}={}", name, pin, (byte >> pin) & 1)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/**
+ * This function validates a register write against the implemented bits of the register.
+ * It must receive the register and the byte to be written
+ */
+pub fn check_reserved_bits<E>(register: Register, value: u8) -> Result<(), Error<E>> {
+    if value & !register.implemented_mask() != 0 {
+        return Err(Error::Protocol(ProtocolError::ReservedBits));
+    }
+    Ok(())
+}
+
+/**
+ * This function applies the INT pin output type (ODR and INTPOL bits) to an IOCON byte.
+ * INTPOL is cleared in open-drain mode since ODR overrides it
+ */
+pub fn iocon_interrupt_output(iocon: u8, output: InterruptOutput) -> u8 {
+    let iocon = iocon & !(IOCON_ODR | IOCON_INTPOL);
+
+    match output {
+        InterruptOutput::ActiveLow => iocon,
+        InterruptOutput::ActiveHigh => iocon | IOCON_INTPOL,
+        InterruptOutput::OpenDrain => iocon | IOCON_ODR,
+    }
+}
+
+/**
+ * This function classifies an output pin from its IODIR, OLAT and GPIO bytes. It must
+ * receive the three register bytes and the pin number to be classified, pins configured
+ * as input are reported as NotAnOutput
+ */
+pub fn classify_output(iodir: u8, olat: u8, gpio: u8, pin: PinNumber) -> PinVerify {
+    if bit_read(iodir, pin) == 1 {
+        return PinVerify::NotAnOutput;
+    }
+
+    match (bit_read(olat, pin), bit_read(gpio, pin)) {
+        (1, 0) => PinVerify::ShortToGround,
+        (0, 1) => PinVerify::ShortToVcc,
+        _ => PinVerify::Driving,
+    }
+}
+
+/**
+ * This function feeds a byte into a CRC-16/CCITT-FALSE checksum. It must receive
+ * the current checksum (0xFFFF on the first byte) and the byte to be added
+ */
+pub fn crc16_update(crc: u16, byte: u8) -> u16 {
+    let mut crc = crc ^ ((byte as u16) << 8);
+    for _ in 0..8 {
+        crc = if crc & 0x8000 != 0 {
+            (crc << 1) ^ 0x1021
+        } else {
+            crc << 1
+        };
+    }
+    crc
+}
+
 #[cfg(test)]
 mod tests {
     use std::println;
@@ -220,6 +532,31 @@ mod tests {
         assert_eq!(0b01111111, value);
     }
 
+    #[test]
+    fn test_bank_from_iocon() {
+        assert_eq!(
+            Some(Bank::Bank0),
+            bank_from_iocon([0x00, 0x00], [0x40, 0x40])
+        );
+        assert_eq!(
+            Some(Bank::Bank1),
+            bank_from_iocon([0xc0, 0xc0], [0x12, 0x00])
+        );
+        // bank 0 with GPINTENB = OLATB = 0x80, 0x0B is not 0 so it cannot be bank 1
+        assert_eq!(
+            Some(Bank::Bank0),
+            bank_from_iocon([0x80, 0x80], [0x40, 0x40])
+        );
+        // bit 0 is unimplemented in IOCON, 0x05/0x15 cannot be IOCON
+        assert_eq!(
+            Some(Bank::Bank0),
+            bank_from_iocon([0x81, 0x81], [0x00, 0x00])
+        );
+        // IOCON = 0 with GPINTENB = OLATB = 0x80 reads like BANK = 1 with OLATA = 0
+        assert_eq!(None, bank_from_iocon([0x80, 0x80], [0x00, 0x00]));
+        assert_eq!(None, bank_from_iocon([0x00, 0x00], [0x12, 0x00]));
+    }
+
     #[test]
     fn test_bit_read() {
         let mut value = 0b10000000;
@@ -229,4 +566,220 @@ mod tests {
         println!("value 0b{:08b}", value);
         assert_eq!(0b00000001, value);
     }
+
+    #[test]
+    fn test_bit_level() {
+        assert_eq!(PinSet::High, bit_level(0b10000000, PinNumber::Pin7));
+        assert_eq!(PinSet::Low, bit_level(0b01111111, PinNumber::Pin7));
+    }
+
+    #[test]
+    fn test_format_byte() {
+        let mut text = std::string::String::new();
+
+        format_byte(0x81, MyPort::Porta, FormatStyle::Binary, &mut text).unwrap();
+        assert_eq!("0b10000001", text);
+
+        text.clear();
+        format_byte(0x81, MyPort::Porta, FormatStyle::Hex, &mut text).unwrap();
+        assert_eq!("0x81", text);
+
+        text.clear();
+        format_byte(0x81, MyPort::Portb, FormatStyle::Named, &mut text).unwrap();
+        assert_eq!(
+            "GPB0=1 GPB1=0 GPB2=0 GPB3=0 GPB4=0 GPB5=0 GPB6=0 GPB7=1",
+            text
+        );
+    }
+
+    #[test]
+    fn test_check_reserved_bits() {
+        assert_eq!(Ok(()), check_reserved_bits::<()>(Register::Gpio, 0xff));
+        assert_eq!(Ok(()), check_reserved_bits::<()>(Register::Iocon, 0xfe));
+        assert_eq!(
+            Err(Error::Protocol(ProtocolError::ReservedBits)),
+            check_reserved_bits::<()>(Register::Iocon, 0x01)
+        );
+        assert_eq!(
+            Err(Error::Protocol(ProtocolError::ReservedBits)),
+            check_reserved_bits::<()>(Register::Intf, 0x10)
+        );
+    }
+
+    #[test]
+    fn test_pin_number_conversions() {
+        for (index, pin) in PinNumber::ALL.iter().enumerate() {
+            assert_eq!(Ok(*pin), PinNumber::try_from(index as u8));
+            assert_eq!(index as u8, u8::from(*pin));
+        }
+        assert_eq!(Err(ProtocolError::InvalidParameter), PinNumber::try_from(8));
+    }
+
+    #[test]
+    fn test_port_conversions() {
+        for port in MyPort::ALL {
+            assert_eq!(Ok(port), MyPort::try_from(u8::from(port)));
+        }
+        assert_eq!(Err(ProtocolError::InvalidParameter), MyPort::try_from(2));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn proptest_pin_number_round_trip(value in proptest::prelude::any::<u8>()) {
+            match PinNumber::try_from(value) {
+                Ok(pin) => proptest::prop_assert_eq!(value, u8::from(pin)),
+                Err(e) => {
+                    proptest::prop_assert!(value > 7);
+                    proptest::prop_assert_eq!(ProtocolError::InvalidParameter, e);
+                }
+            }
+        }
+
+        #[test]
+        fn proptest_port_round_trip(value in proptest::prelude::any::<u8>()) {
+            match MyPort::try_from(value) {
+                Ok(port) => proptest::prop_assert_eq!(value, u8::from(port)),
+                Err(_) => proptest::prop_assert!(value > 1),
+            }
+        }
+
+        #[test]
+        fn proptest_gpio_name_round_trip(value in proptest::prelude::any::<u8>()) {
+            match GpioName::try_from(value) {
+                Ok(name) => {
+                    proptest::prop_assert_eq!(value, u8::from(name));
+                    let (port, pin): (MyPort, PinNumber) = name.into();
+                    proptest::prop_assert_eq!(name, GpioName::from((port, pin)));
+                }
+                Err(_) => proptest::prop_assert!(value > 15),
+            }
+        }
+    }
+
+    #[test]
+    fn test_gpio_name() {
+        use std::string::ToString;
+
+        for (index, name) in GPIO_NAMES.iter().enumerate() {
+            let (port, pin): (MyPort, PinNumber) = (*name).into();
+            assert_eq!(*name as usize, index);
+            assert_eq!(*name, GpioName::from((port, pin)));
+        }
+        assert_eq!((MyPort::Portb, PinNumber::Pin3), GpioName::GPB3.into());
+        assert_eq!("GPA7", GpioName::GPA7.to_string());
+        assert_eq!("GPB0", GpioName::GPB0.to_string());
+    }
+
+    #[test]
+    fn test_classify_output() {
+        assert_eq!(
+            PinVerify::Driving,
+            classify_output(0x00, 0x01, 0x01, PinNumber::Pin0)
+        );
+        assert_eq!(
+            PinVerify::Driving,
+            classify_output(0x00, 0x00, 0x00, PinNumber::Pin0)
+        );
+        assert_eq!(
+            PinVerify::ShortToGround,
+            classify_output(0x00, 0x01, 0x00, PinNumber::Pin0)
+        );
+        assert_eq!(
+            PinVerify::ShortToVcc,
+            classify_output(0x00, 0x00, 0x01, PinNumber::Pin0)
+        );
+        assert_eq!(
+            PinVerify::NotAnOutput,
+            classify_output(0x01, 0x01, 0x01, PinNumber::Pin0)
+        );
+        assert_eq!(
+            PinVerify::NotAnOutput,
+            classify_output(0x80, 0x00, 0x80, PinNumber::Pin7)
+        );
+        assert_eq!(
+            PinVerify::Driving,
+            classify_output(0x80, 0x00, 0x80, PinNumber::Pin6)
+        );
+    }
+
+    #[test]
+    fn test_crc16_update() {
+        let crc = b"123456789"
+            .iter()
+            .fold(0xFFFF, |crc, byte| crc16_update(crc, *byte));
+
+        println!("crc 0x{:04x}", crc);
+        assert_eq!(0x29b1, crc);
+    }
+
+    #[test]
+    fn test_error_split() {
+        use std::string::ToString;
+
+        let transport: Error<embedded_hal::i2c::ErrorKind> =
+            i2c_comm_error(embedded_hal::i2c::ErrorKind::Other);
+        let protocol: Error<embedded_hal::i2c::ErrorKind> = ProtocolError::PinIsNotInput.into();
+
+        assert_eq!(
+            Error::Transport(embedded_hal::i2c::ErrorKind::Other),
+            transport
+        );
+        assert_eq!(Error::Protocol(ProtocolError::PinIsNotInput), protocol);
+        assert_eq!("Pin is not Input", protocol.to_string());
+    }
+
+    #[test]
+    fn test_iocon_interrupt_output() {
+        assert_eq!(
+            0x40,
+            iocon_interrupt_output(0x46, InterruptOutput::ActiveLow)
+        );
+        assert_eq!(
+            0x42,
+            iocon_interrupt_output(0x44, InterruptOutput::ActiveHigh)
+        );
+        assert_eq!(
+            0x44,
+            iocon_interrupt_output(0x42, InterruptOutput::OpenDrain)
+        );
+    }
+
+    #[test]
+    fn test_pin_flags() {
+        let flags = PinFlags(0b1000_0101);
+
+        assert!(flags.contains(PinNumber::Pin2));
+        assert!(!flags.contains(PinNumber::Pin1));
+        assert_eq!(3, flags.len());
+        assert!(!flags.is_empty());
+        assert!(PinFlags::default().is_empty());
+
+        let pins: std::vec::Vec<PinNumber> = flags.collect();
+        assert_eq!(
+            std::vec![PinNumber::Pin0, PinNumber::Pin2, PinNumber::Pin7],
+            pins
+        );
+    }
+
+    #[test]
+    fn test_error_i2c_kind() {
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+
+        let nack: Error<ErrorKind> =
+            Error::Transport(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        assert_eq!(
+            Some(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+            nack.i2c_kind()
+        );
+
+        let protocol: Error<ErrorKind> = Error::Protocol(ProtocolError::InvalidParameter);
+        assert_eq!(None, protocol.i2c_kind());
+    }
+
+    #[test]
+    fn test_iter() {
+        assert!(PinNumber::iter().eq(PIN_NUMBERS));
+        assert!(PinNumber::iter().map(u8::from).eq(0..8));
+        assert!(MyPort::iter().eq([MyPort::Porta, MyPort::Portb]));
+    }
 }
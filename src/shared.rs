@@ -0,0 +1,246 @@
+use core::cell::{Cell, RefCell};
+
+use critical_section::Mutex;
+use embedded_hal::i2c::I2c;
+
+use crate::chipmode::MCP23017;
+use crate::prelude::*;
+use crate::registers::OutputReady;
+
+/// Pin write requested from interrupt context and executed later in thread context
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PendingWrite {
+    pub port: MyPort,
+    pub pin: PinNumber,
+    pub value: PinSet,
+}
+
+/// Driver shared between interrupt handlers and the main thread, holding a single
+/// pending pin write slot (no queue), usable as a static
+#[derive(Debug)]
+pub struct IsrShared<D> {
+    driver: Mutex<RefCell<Option<D>>>,
+    pending: Mutex<Cell<Option<PendingWrite>>>,
+}
+
+impl<D> Default for IsrShared<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D> IsrShared<D> {
+    /**
+     * Function used to create an empty shared slot, the driver is handed over with init
+     */
+    #[inline]
+    pub const fn new() -> Self {
+        IsrShared {
+            driver: Mutex::new(RefCell::new(None)),
+            pending: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    /**
+     * Function used to hand the driver over to the shared slot, returns the previous one
+     */
+    #[inline]
+    pub fn init(&self, driver: D) -> Option<D> {
+        critical_section::with(|cs| self.driver.borrow_ref_mut(cs).replace(driver))
+    }
+
+    /**
+     * Function used to take the driver back from the shared slot
+     */
+    #[inline]
+    pub fn release(&self) -> Option<D> {
+        critical_section::with(|cs| self.driver.borrow_ref_mut(cs).take())
+    }
+
+    /**
+     * Function used to run a closure with exclusive access to the driver inside a
+     * critical section, returns None if no driver was handed over
+     */
+    #[inline]
+    pub fn lock<R>(&self, f: impl FnOnce(&mut D) -> R) -> Option<R> {
+        critical_section::with(|cs| self.driver.borrow_ref_mut(cs).as_mut().map(f))
+    }
+
+    /**
+     * Function used (typically from an ISR) to request a pin write, returns false and
+     * keeps the older request if the slot is already taken
+     */
+    #[inline]
+    pub fn request_write(&self, port: MyPort, pin: PinNumber, value: PinSet) -> bool {
+        critical_section::with(|cs| {
+            let pending = self.pending.borrow(cs);
+            if pending.get().is_some() {
+                return false;
+            }
+            pending.set(Some(PendingWrite { port, pin, value }));
+            true
+        })
+    }
+
+    /**
+     * Function used to check whether a write request is waiting to be serviced
+     */
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        critical_section::with(|cs| self.pending.borrow(cs).get().is_some())
+    }
+
+    /**
+     * Function used to take the pending write request, freeing the slot
+     */
+    #[inline]
+    pub fn take_pending(&self) -> Option<PendingWrite> {
+        critical_section::with(|cs| self.pending.borrow(cs).take())
+    }
+}
+
+impl<I2C, E> IsrShared<MCP23017<I2C, OutputReady>>
+where
+    I2C: I2c<Error = E>,
+{
+    /**
+     * Function used in thread context to execute the pending write request, returns the
+     * serviced request or None if the slot was empty or no driver was handed over.
+     * The driver is moved out of the shared slot for the bus transfer so interrupts are
+     * not masked during I2C traffic (lock returns None meanwhile), and the request is only
+     * cleared once applied: it stays pending on a bus error or without a driver
+     */
+    #[inline]
+    pub fn service(&self) -> Result<Option<PendingWrite>, Error<E>> {
+        let Some(request) = critical_section::with(|cs| self.pending.borrow(cs).get()) else {
+            return Ok(None);
+        };
+
+        let Some(mut mcp) = self.release() else {
+            return Ok(None);
+        };
+        let result = mcp.write_pin(request.port, request.pin, request.value);
+
+        critical_section::with(|cs| {
+            self.driver.borrow_ref_mut(cs).replace(mcp);
+            if result.is_ok() {
+                self.pending.borrow(cs).set(None);
+            }
+        });
+
+        result.map(|_| Some(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate embedded_hal_mock;
+    extern crate std;
+    use crate::registers::Register;
+    use embedded_hal::i2c::ErrorKind;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use std::vec;
+    use std::vec::Vec;
+
+    fn vector1(a: u8) -> Vec<u8> {
+        vec![a]
+    }
+
+    fn vector2(a: u8, b: u8) -> Vec<u8> {
+        vec![a, b]
+    }
+
+    fn vector3(a: u8, b: u8, c: u8) -> Vec<u8> {
+        vec![a, b, c]
+    }
+
+    #[test]
+    fn test_request_write() {
+        let shared: IsrShared<u8> = IsrShared::new();
+
+        assert!(!shared.is_pending());
+        assert!(shared.request_write(MyPort::Porta, PinNumber::Pin1, PinSet::High));
+        assert!(!shared.request_write(MyPort::Portb, PinNumber::Pin2, PinSet::Low));
+        assert!(shared.is_pending());
+        assert_eq!(
+            Some(PendingWrite {
+                port: MyPort::Porta,
+                pin: PinNumber::Pin1,
+                value: PinSet::High,
+            }),
+            shared.take_pending()
+        );
+        assert!(!shared.is_pending());
+    }
+
+    #[test]
+    fn test_lock() {
+        let shared: IsrShared<u8> = IsrShared::new();
+
+        assert_eq!(None, shared.lock(|value| *value));
+        assert_eq!(None, shared.init(5));
+        assert_eq!(Some(6), shared.lock(|value| *value + 1));
+        assert_eq!(Some(5), shared.release());
+        assert_eq!(None, shared.release());
+    }
+
+    #[test]
+    fn test_service_without_driver() {
+        let shared: IsrShared<MCP23017<I2cMock, OutputReady>> = IsrShared::new();
+
+        assert!(shared.request_write(MyPort::Porta, PinNumber::Pin3, PinSet::Low));
+        assert_eq!(None, shared.service().unwrap());
+        assert!(shared.is_pending());
+        assert_eq!(
+            Some(PendingWrite {
+                port: MyPort::Porta,
+                pin: PinNumber::Pin3,
+                value: PinSet::Low,
+            }),
+            shared.take_pending()
+        );
+    }
+
+    #[test]
+    fn test_service() {
+        let expectations = [
+            I2cTransaction::write(0x40, vector3(Register::Iodir as u8, 0x00, 0x00)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x00)),
+            I2cTransaction::write(0x40, vector3(Register::Gpio as u8, 0x00, 0x04)),
+            I2cTransaction::write_read(0x40, vector1(Register::Gpio as u8), vector2(0x00, 0x04))
+                .with_error(ErrorKind::Other),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mcp = MCP23017::new(i2c.clone(), 0x40).set_as_output().unwrap();
+
+        let shared = IsrShared::new();
+        assert_eq!(None, shared.service().unwrap());
+
+        shared.init(mcp);
+        assert_eq!(None, shared.service().unwrap());
+
+        assert!(shared.request_write(MyPort::Portb, PinNumber::Pin2, PinSet::High));
+        assert_eq!(
+            Some(PendingWrite {
+                port: MyPort::Portb,
+                pin: PinNumber::Pin2,
+                value: PinSet::High,
+            }),
+            shared.service().unwrap()
+        );
+
+        assert!(shared.request_write(MyPort::Porta, PinNumber::Pin0, PinSet::High));
+        assert_eq!(
+            Error::Transport(ErrorKind::Other),
+            shared.service().unwrap_err()
+        );
+        assert!(shared.is_pending());
+        assert!(shared.lock(|_| ()).is_some());
+
+        //finalize execution
+        shared.release();
+        i2c.done();
+    }
+}